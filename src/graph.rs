@@ -1,9 +1,18 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::io::Error;
-use std::ops::Range;
-use std::{fmt, fs};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, Read};
+use std::str::FromStr;
+use std::{fmt, fs, io};
 
-#[derive(Debug)]
+use chrono::{DateTime, NaiveDateTime};
+use flate2::read::GzDecoder;
+use log::debug;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 pub struct Contact {
     pub couple: (i32, i32),
     pub start: i32,
@@ -14,19 +23,109 @@ pub struct Contact {
 ///
 /// * `StartEnd`: each line follows the format n1 n2 ts te
 /// * `CreateDelete`: a line `t n1 n2 C` for a contact creation and a line `t n1 n2 S` for contact suppression
+/// * `Tij`: a line `t n1 n2` each time the contact is sighted, as in the SocioPatterns datasets.
+///   Sightings are taken every `period` and consecutive sightings of the same pair are merged in
+///   a single contact as long as at most `gap` samples are missing between them
+/// * `Gexf`: dynamic GEXF graph for Gephi, each pair of nodes being an edge with one spell per
+///   contact (write only)
+/// * `GraphMl`: GraphML graph with one edge per contact carrying its start and end times (write
+///   only)
+/// * `Json`: serialization of the whole `Graph`, labels and time reference included (requires the
+///   `json` feature)
+/// * `Binary`: compact versioned binary dump of the whole `Graph`, fast to reload (see
+///   `Graph::to_binary` for the layout)
+#[derive(Debug, Clone, Copy)]
 pub enum GraphFileFormat {
     StartEnd,
     CreateDelete,
+    Tij {
+        period: i32,
+        gap: i32,
+    },
+    Gexf,
+    GraphMl,
+    Binary,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl FromStr for GraphFileFormat {
+    type Err = String;
+
+    /// Parse a format name as used on the command line (`start-end`, `create-delete`, `tij`,
+    /// `gexf`, `graphml`, `binary` or `json`)
+    ///
+    /// `tij` uses the SocioPatterns sampling period of 20 and does not tolerate any gap.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "start-end" => Ok(GraphFileFormat::StartEnd),
+            "create-delete" => Ok(GraphFileFormat::CreateDelete),
+            "tij" => Ok(GraphFileFormat::Tij { period: 20, gap: 0 }),
+            "gexf" => Ok(GraphFileFormat::Gexf),
+            "graphml" => Ok(GraphFileFormat::GraphMl),
+            "binary" => Ok(GraphFileFormat::Binary),
+            #[cfg(feature = "json")]
+            "json" => Ok(GraphFileFormat::Json),
+            _ => Err(format!("unknown graph file format: {}", s)),
+        }
+    }
+}
+
+/// First bytes of a `GraphFileFormat::Binary` file
+const BINARY_MAGIC: &[u8; 8] = b"GRAPHIA\0";
+
+/// Version of the `GraphFileFormat::Binary` layout, to be increased on every change
+const BINARY_VERSION: u32 = 2;
+
+/// Largest number of labels allocated in advance when reading a binary file, so that a corrupted
+/// header cannot exhaust the memory
+const BINARY_MAX_CAPACITY: usize = 1 << 16;
+
+/// Options used when reading a graph file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOptions {
+    /// Skip and count the malformed lines instead of stopping at the first one
+    pub lenient: bool,
+    /// Width of a time sample, in the time unit of the file (seconds for dates). Defaults to 1, or
+    /// to the sampling period for `GraphFileFormat::Tij`
+    pub time_step: Option<f64>,
+}
+
+/// Transitions of the pairs of nodes from a snapshot of a `Graph` to the next one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Transitions {
+    /// Number of linked pairs in the first snapshot
+    pub links: u64,
+    /// Number of pairs not linked in the first snapshot
+    pub non_links: u64,
+    /// Number of pairs linked in the second snapshot but not in the first one
+    pub created: u64,
+    /// Number of pairs linked in the first snapshot but not in the second one
+    pub deleted: u64,
 }
 
 /// Describes a non stationnary Graph
 ///
-/// contacts must be ordered by contact starting time.
+/// contacts must be ordered by contact starting time. When the graph is read from a file, the nodes
+/// are dense indices and `labels[i]` holds the original identifier of the node `i`. Graphs without
+/// labels (e.g. simulated ones) use the node index as label.
+///
+/// Contact times are samples of width `time_step`, the sample 0 being at the time `epoch` of the
+/// original trace.
+///
+/// `seed` is the seed of the random generator that produced a simulated graph, written in the
+/// metadata of the saved files so that the simulation can be repeated.
 #[derive(Debug)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 pub struct Graph {
     pub nodes: Vec<i32>,
     pub contacts: Vec<Contact>,
     pub duration: i32,
+    pub labels: Vec<String>,
+    pub epoch: f64,
+    pub time_step: f64,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub seed: Option<u64>,
 }
 
 impl Default for Graph {
@@ -36,10 +135,48 @@ impl Default for Graph {
             nodes: vec![],
             contacts: vec![],
             duration: 0,
+            labels: vec![],
+            epoch: 0.0,
+            time_step: 1.0,
+            seed: None,
         }
     }
 }
 
+/// Maps arbitrary node labels (integers, MAC addresses, badge IDs...) to dense node indices
+///
+/// Indices are given in order of first appearance, starting at 0, so that reading the same file
+/// always gives the same mapping.
+#[derive(Debug, Default)]
+pub struct NodeInterner {
+    indices: HashMap<String, i32>,
+    labels: Vec<String>,
+}
+
+impl NodeInterner {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Get the index of `label`, assigning the next free index if it has never been seen
+    pub fn intern(&mut self, label: &str) -> i32 {
+        if let Some(&index) = self.indices.get(label) {
+            return index;
+        }
+
+        let index = self.labels.len() as i32;
+        self.indices.insert(label.to_string(), index);
+        self.labels.push(label.to_string());
+
+        index
+    }
+
+    /// Consume the interner and return the labels indexed by node index
+    pub fn into_labels(self) -> Vec<String> {
+        self.labels
+    }
+}
+
 impl fmt::Display for Graph {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -52,8 +189,89 @@ impl fmt::Display for Graph {
     }
 }
 
+/// Error raised while reading a graph file
+///
+/// Line numbers start at 1 and `text` holds the offending line.
+#[derive(Debug)]
+pub enum GraphParseError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The file does not contain any contact
+    EmptyFile,
+    /// A column that should hold a time could not be parsed
+    BadTime { line: usize, text: String },
+    /// A line does not have the number of columns required by the format
+    WrongColumnCount {
+        line: usize,
+        text: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A contact ends before it starts
+    EndBeforeStart { line: usize, text: String },
+    /// An event is neither a creation (`C`) nor a suppression (`S`)
+    BadEvent { line: usize, text: String },
+    /// The format can only be written
+    UnsupportedFormat(GraphFileFormat),
+    /// The binary file is not a graph written by `Graph::save` or uses an unknown version
+    BadBinary(String),
+    /// The JSON document does not describe a graph
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for GraphParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphParseError::Io(e) => write!(f, "{}", e),
+            GraphParseError::EmptyFile => write!(f, "the file does not contain any contact"),
+            GraphParseError::BadTime { line, text } => write!(
+                f,
+                "line {}: expected times as numbers or ISO-8601 dates in \"{}\"",
+                line, text
+            ),
+            GraphParseError::WrongColumnCount {
+                line,
+                text,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} columns, found {} in \"{}\"",
+                line, expected, found, text
+            ),
+            GraphParseError::EndBeforeStart { line, text } => {
+                write!(
+                    f,
+                    "line {}: contact ends before it starts in \"{}\"",
+                    line, text
+                )
+            }
+            GraphParseError::BadEvent { line, text } => write!(
+                f,
+                "line {}: event should be either C or S in \"{}\"",
+                line, text
+            ),
+            GraphParseError::UnsupportedFormat(format) => {
+                write!(f, "reading {:?} files is not supported", format)
+            }
+            GraphParseError::BadBinary(reason) => write!(f, "invalid binary graph: {}", reason),
+            #[cfg(feature = "json")]
+            GraphParseError::Json(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for GraphParseError {}
+
+impl From<io::Error> for GraphParseError {
+    fn from(e: io::Error) -> Self {
+        GraphParseError::Io(e)
+    }
+}
+
 impl TryFrom<String> for Graph {
-    type Error = &'static str;
+    type Error = GraphParseError;
 
     /// Read a graph from a String
     ///
@@ -63,58 +281,24 @@ impl TryFrom<String> for Graph {
     /// which the last contact between n1 and n2 has been recorded. It is worth
     /// noticing that the contacts are undirected and that, by convention, n1 < n2
     fn try_from(s: String) -> Result<Graph, Self::Error> {
-        // Convert lines into array of int
-        let mut contacts: Vec<Contact> = s
-            .lines()
-            .map(|l| {
-                l.split(" ")
-                    .map(|s| s.parse::<i32>().expect("Parse error"))
-                    .collect()
-            })
-            .map(|c: Vec<i32>| Contact {
-                couple: (c[0], c[1]),
-                start: c[2],
-                end: c[3],
-            })
-            .collect();
-
-        // Make the time start at zero
-        let t_start: i32 = (&contacts).into_iter().map(|c| c.start).min().unwrap();
-
-        // Change the time reference to start at 0
-        for contact in &mut contacts {
-            contact.start -= t_start;
-            contact.end -= t_start;
-        }
-        let last_node = contacts.last().unwrap().couple.1;
-
-        // Sort contacts by starting time
-        contacts.sort_by(|a, b| a.start.cmp(&b.start));
-
-        let mut g = Graph {
-            nodes: Range {
-                start: 1,
-                end: last_node,
-            }
-            .collect(),
-            contacts: contacts,
-            ..Default::default()
-        };
-        g.update_duration();
-
-        Ok(g)
+        Self::from_start_end(s.as_bytes(), false, 1.0).map(|(graph, _)| graph)
     }
 }
 
 impl From<&Graph> for String {
     fn from(graph: &Graph) -> String {
-        let contacts = graph
-            .contacts
-            .iter()
-            .map(|c| format!("{} {} {} {} \n", c.couple.0, c.couple.1, c.start, c.end));
+        let contacts = graph.contacts.iter().map(|c| {
+            let (n1, n2) = graph.ordered_couple(c.couple);
+            format!(
+                "{} {} {} {} \n",
+                graph.label(n1),
+                graph.label(n2),
+                graph.time(c.start),
+                graph.time(c.end)
+            )
+        });
 
-        let s: String = contacts.collect();
-        return s;
+        graph.comment_header() + &contacts.collect::<String>()
     }
 }
 
@@ -127,14 +311,692 @@ impl Graph {
     /// time at which the last contact between n1 and n2 has been recorded. It
     /// is worth noticing that the contacts are undirected and that, by
     /// convention, n1 < n2
-    pub fn from_file(filename: &str) -> Result<Graph, Error> {
-        let graph_string = fs::read_to_string(filename)?;
+    ///
+    /// `-` reads the standard input and files ending with `.gz` are read as gzip compressed
+    pub fn from_file(filename: &str) -> Result<Graph, GraphParseError> {
+        Self::from_file_with_format(filename, GraphFileFormat::StartEnd)
+    }
+
+    /// Read a graph from a file following the given `GraphFileFormat`
+    ///
+    /// Parsing stops at the first malformed line.
+    pub fn from_file_with_format(
+        filename: &str,
+        file_format: GraphFileFormat,
+    ) -> Result<Graph, GraphParseError> {
+        Self::from_file_with_options(filename, file_format, ReadOptions::default())
+            .map(|(graph, _)| graph)
+    }
+
+    /// Read a graph from a file following the given `GraphFileFormat` and `ReadOptions`
+    ///
+    /// Returns the graph along with the number of skipped lines.
+    pub fn from_file_with_options(
+        filename: &str,
+        file_format: GraphFileFormat,
+        options: ReadOptions,
+    ) -> Result<(Graph, usize), GraphParseError> {
+        Self::from_reader(Self::open_file(filename)?, file_format, options)
+    }
+
+    /// Open `filename` for buffered reading
+    ///
+    /// `-` stands for the standard input and files ending with `.gz` are decompressed on the fly.
+    fn open_file(filename: &str) -> Result<Box<dyn BufRead>, GraphParseError> {
+        if filename == "-" {
+            return Ok(Box::new(BufReader::new(io::stdin())));
+        }
+
+        let file = File::open(filename)?;
+
+        if filename.ends_with(".gz") {
+            Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+        } else {
+            Ok(Box::new(BufReader::new(file)))
+        }
+    }
+
+    /// Read a graph from any buffered reader following the given `GraphFileFormat`
+    ///
+    /// The input is parsed line by line so that the text of the trace is never held in memory.
+    /// In lenient mode, the malformed lines are skipped and their number is returned along with
+    /// the graph.
+    pub fn from_reader<R: BufRead>(
+        reader: R,
+        file_format: GraphFileFormat,
+        options: ReadOptions,
+    ) -> Result<(Graph, usize), GraphParseError> {
+        let lenient = options.lenient;
+
+        match file_format {
+            GraphFileFormat::StartEnd => {
+                Self::from_start_end(reader, lenient, options.time_step.unwrap_or(1.0))
+            }
+            GraphFileFormat::CreateDelete => {
+                Self::from_create_delete(reader, lenient, options.time_step.unwrap_or(1.0))
+            }
+            GraphFileFormat::Tij { period, gap } => {
+                let time_step = options.time_step.unwrap_or(period as f64);
+                Self::from_tij(reader, period, gap, lenient, time_step)
+            }
+            GraphFileFormat::Gexf | GraphFileFormat::GraphMl => {
+                Err(GraphParseError::UnsupportedFormat(file_format))
+            }
+            GraphFileFormat::Binary => {
+                let graph = Self::from_binary(reader)?;
+                Ok((graph.rebin_option(options.time_step), 0))
+            }
+            #[cfg(feature = "json")]
+            GraphFileFormat::Json => {
+                let graph: Graph =
+                    serde_json::from_reader(reader).map_err(GraphParseError::Json)?;
+                Ok((graph.rebin_option(options.time_step), 0))
+            }
+        }
+    }
+
+    /// Read a label mapping file, where each line `old new` gives the new label of a node
+    pub fn read_labels(filename: &str) -> Result<HashMap<String, String>, GraphParseError> {
+        let (mapping, _) = Self::parse_lines(Self::open_file(filename)?, false, |line, text| {
+            let columns = Self::split_columns(line, text, 2)?;
+
+            Ok((columns[0].to_string(), columns[1].to_string()))
+        })?;
+
+        Ok(mapping.into_iter().collect())
+    }
+
+    /// Split a line in exactly `expected` columns
+    fn split_columns(
+        line: usize,
+        text: &str,
+        expected: usize,
+    ) -> Result<Vec<&str>, GraphParseError> {
+        let columns: Vec<&str> = text.split_whitespace().collect();
+
+        if columns.len() != expected {
+            return Err(GraphParseError::WrongColumnCount {
+                line,
+                text: text.to_string(),
+                expected,
+                found: columns.len(),
+            });
+        }
+
+        Ok(columns)
+    }
+
+    /// Parse a time column of a line
+    ///
+    /// Times are either numbers (integer samples or Unix timestamps with fractional seconds) or
+    /// ISO-8601 dates, which are converted to Unix timestamps. Dates without offset are in UTC.
+    fn parse_time(line: usize, text: &str, column: &str) -> Result<f64, GraphParseError> {
+        if let Ok(t) = column.parse::<f64>() {
+            if t.is_finite() {
+                return Ok(t);
+            }
+        }
+
+        let date = DateTime::parse_from_rfc3339(column)
+            .map(|date| date.naive_utc())
+            .or_else(|_| NaiveDateTime::parse_from_str(column, "%Y-%m-%dT%H:%M:%S%.f"));
+
+        match date {
+            Ok(date) => {
+                let date = date.and_utc();
+                Ok(date.timestamp() as f64 + date.timestamp_subsec_nanos() as f64 * 1e-9)
+            }
+            Err(_) => Err(GraphParseError::BadTime {
+                line,
+                text: text.to_string(),
+            }),
+        }
+    }
+
+    /// Index of the sample of width `time_step` containing the time `t`
+    fn sample(t: f64, epoch: f64, time_step: f64) -> i32 {
+        // Absorb rounding errors on times falling exactly on a sample boundary
+        ((t - epoch) / time_step + 1e-9).floor() as i32
+    }
+
+    /// Absolute time of a sample, in the time unit of the original trace
+    pub fn time(&self, sample: i32) -> f64 {
+        self.epoch + sample as f64 * self.time_step
+    }
+
+    /// Parse every non blank line of `reader` with `parse_line`
+    ///
+    /// In lenient mode, the malformed lines are skipped and counted instead of stopping the
+    /// parsing.
+    fn parse_lines<R, T, F>(
+        mut reader: R,
+        lenient: bool,
+        mut parse_line: F,
+    ) -> Result<(Vec<T>, usize), GraphParseError>
+    where
+        R: BufRead,
+        F: FnMut(usize, &str) -> Result<T, GraphParseError>,
+    {
+        let mut parsed: Vec<T> = Vec::new();
+        let mut skipped: usize = 0;
+        let mut buffer = String::new();
+        let mut line: usize = 0;
+
+        loop {
+            buffer.clear();
+            if reader.read_line(&mut buffer)? == 0 {
+                break;
+            }
+            line += 1;
+
+            let text = buffer.trim_end();
+            if text.trim().is_empty() || text.starts_with('#') {
+                continue;
+            }
+
+            match parse_line(line, text) {
+                Ok(item) => parsed.push(item),
+                Err(e) if lenient => {
+                    debug!("skipping {}", e);
+                    skipped += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        if parsed.is_empty() {
+            return Err(GraphParseError::EmptyFile);
+        }
+
+        Ok((parsed, skipped))
+    }
+
+    /// Intern the labels of the two nodes of a contact
+    ///
+    /// Contacts are undirected, the couple is ordered so that n1 < n2.
+    fn couple(interner: &mut NodeInterner, label1: &str, label2: &str) -> (i32, i32) {
+        let n1 = interner.intern(label1);
+        let n2 = interner.intern(label2);
+
+        (n1.min(n2), n1.max(n2))
+    }
+
+    /// Build a graph from parsed contacts
+    ///
+    /// The contacts are sorted by starting time and the nodes are the ones involved in at least
+    /// one contact. When the time has been rebinned, the contacts of a pair overlapping in the
+    /// same samples are merged.
+    fn from_contacts(
+        mut contacts: Vec<Contact>,
+        interner: NodeInterner,
+        epoch: f64,
+        time_step: f64,
+    ) -> Graph {
+        if (time_step - 1.0).abs() > f64::EPSILON {
+            contacts = Self::merge_overlapping(contacts);
+        }
+
+        // Sort contacts by starting time
+        contacts.sort_by_key(|c| c.start);
+
+        let mut nodes: Vec<i32> = contacts
+            .iter()
+            .flat_map(|c| vec![c.couple.0, c.couple.1])
+            .collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+
+        let mut g = Graph {
+            nodes,
+            contacts,
+            labels: interner.into_labels(),
+            epoch,
+            time_step,
+            ..Default::default()
+        };
+        g.update_duration();
+
+        g
+    }
+
+    /// Merge the contacts of a same pair that overlap
+    fn merge_overlapping(mut contacts: Vec<Contact>) -> Vec<Contact> {
+        contacts.sort_by_key(|c| (c.couple, c.start));
+
+        let mut merged: Vec<Contact> = Vec::with_capacity(contacts.len());
+        for contact in contacts {
+            match merged.last_mut() {
+                Some(last) if last.couple == contact.couple && contact.start <= last.end => {
+                    last.end = last.end.max(contact.end);
+                }
+                _ => merged.push(contact),
+            }
+        }
+
+        merged
+    }
+
+    /// Change the width of the time samples to `time_step`, in the time unit of the original trace
+    ///
+    /// The contacts of a pair overlapping in the new samples are merged.
+    pub fn rebin(mut self, time_step: f64) -> Graph {
+        for contact in &mut self.contacts {
+            contact.start = Self::sample(
+                self.epoch + contact.start as f64 * self.time_step,
+                self.epoch,
+                time_step,
+            );
+            contact.end = Self::sample(
+                self.epoch + contact.end as f64 * self.time_step,
+                self.epoch,
+                time_step,
+            );
+        }
+
+        self.contacts = Self::merge_overlapping(self.contacts);
+        self.contacts.sort_by_key(|c| c.start);
+        self.time_step = time_step;
+        self.update_duration();
+
+        self
+    }
+
+    /// Rebin the graph only if a time step is given
+    fn rebin_option(self, time_step: Option<f64>) -> Graph {
+        match time_step {
+            Some(time_step) => self.rebin(time_step),
+            None => self,
+        }
+    }
+
+    /// Replace the labels of the nodes found in `mapping`, the other nodes keep their label
+    pub fn relabel(&mut self, mapping: &HashMap<String, String>) {
+        self.materialize_labels();
+
+        for label in self.labels.iter_mut() {
+            if let Some(new_label) = mapping.get(label) {
+                *label = new_label.clone();
+            }
+        }
+    }
+
+    /// Label the nodes from `1` to `n` following their order in `nodes`, hiding the original
+    /// identifiers
+    pub fn anonymise_labels(&mut self) {
+        self.materialize_labels();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            self.labels[*node as usize] = (i + 1).to_string();
+        }
+    }
+
+    /// Give an explicit label to every node, graphs without labels using the node index
+    fn materialize_labels(&mut self) {
+        let n_labels = self
+            .nodes
+            .iter()
+            .map(|&n| n as usize + 1)
+            .max()
+            .unwrap_or(0);
+
+        while self.labels.len() < n_labels {
+            self.labels.push(self.labels.len().to_string());
+        }
+    }
+
+    /// Get the original label of a node
+    pub fn label(&self, node: i32) -> String {
+        match self.labels.get(node as usize) {
+            Some(label) => label.clone(),
+            None => node.to_string(),
+        }
+    }
+
+    /// Order the nodes of a couple by label, so that n1 < n2 in the saved files whatever the order
+    /// in which the labels were interned. Labels that are both integers are compared as numbers
+    fn ordered_couple(&self, couple: (i32, i32)) -> (i32, i32) {
+        let (label1, label2) = (self.label(couple.0), self.label(couple.1));
+        let reversed = match (label1.parse::<i64>(), label2.parse::<i64>()) {
+            (Ok(n1), Ok(n2)) => n1 > n2,
+            _ => label1 > label2,
+        };
+
+        if reversed {
+            (couple.1, couple.0)
+        } else {
+            couple
+        }
+    }
 
-        let graph = Self::try_from(graph_string).unwrap();
-        Ok(graph)
+    /// Read a graph following the `GraphFileFormat::StartEnd` format
+    ///
+    /// Times are binned in samples of width `time_step`, the first contact starting at 0.
+    fn from_start_end<R: BufRead>(
+        reader: R,
+        lenient: bool,
+        time_step: f64,
+    ) -> Result<(Graph, usize), GraphParseError> {
+        let mut interner = NodeInterner::new();
+
+        let (intervals, skipped) = Self::parse_lines(reader, lenient, |line, text| {
+            let columns = Self::split_columns(line, text, 4)?;
+            let start = Self::parse_time(line, text, columns[2])?;
+            let end = Self::parse_time(line, text, columns[3])?;
+
+            if end < start {
+                return Err(GraphParseError::EndBeforeStart {
+                    line,
+                    text: text.to_string(),
+                });
+            }
+
+            Ok((
+                Self::couple(&mut interner, columns[0], columns[1]),
+                start,
+                end,
+            ))
+        })?;
+
+        // Make the time start at zero
+        let epoch = intervals.iter().map(|i| i.1).fold(f64::INFINITY, f64::min);
+
+        let contacts = intervals
+            .into_iter()
+            .map(|(couple, start, end)| Contact {
+                couple,
+                start: Self::sample(start, epoch, time_step),
+                end: Self::sample(end, epoch, time_step),
+            })
+            .collect();
+
+        Ok((
+            Self::from_contacts(contacts, interner, epoch, time_step),
+            skipped,
+        ))
     }
 
-    /// Convert to `String` following the `GraphFileFormat::StartEnd` format
+    /// Read a graph following the `GraphFileFormat::CreateDelete` format
+    ///
+    /// Each creation event is paired with the next suppression event of the same couple. Since the
+    /// suppression is written one time step after the last recorded contact, the contact ends at
+    /// `t - 1`. Unmatched events come from contacts cut by the boundaries of the trace: a
+    /// suppression without creation starts at the first time of the trace and a creation without
+    /// suppression ends at the last time of the trace.
+    ///
+    /// Times are binned in samples of width `time_step`, the first event happening at 0.
+    fn from_create_delete<R: BufRead>(
+        reader: R,
+        lenient: bool,
+        time_step: f64,
+    ) -> Result<(Graph, usize), GraphParseError> {
+        let mut interner = NodeInterner::new();
+
+        let (mut events, skipped) = Self::parse_lines(reader, lenient, |line, text| {
+            let columns = Self::split_columns(line, text, 4)?;
+            let kind = match columns[3] {
+                "C" => 'C',
+                "S" => 'S',
+                _ => {
+                    return Err(GraphParseError::BadEvent {
+                        line,
+                        text: text.to_string(),
+                    })
+                }
+            };
+
+            let t = Self::parse_time(line, text, columns[0])?;
+            let (n1, n2) = Self::couple(&mut interner, columns[1], columns[2]);
+
+            Ok((t, n1, n2, kind))
+        })?;
+        events.sort_by(|e1, e2| e1.0.partial_cmp(&e2.0).unwrap());
+
+        let t_first = events.first().map_or(0.0, |e| e.0);
+        let t_last = events.last().map_or(0.0, |e| e.0);
+
+        // Index in `intervals` of the interval currently opened for each couple. An interval is
+        // (couple, creation time, suppression time)
+        let mut opened: HashMap<(i32, i32), usize> = HashMap::new();
+        let mut intervals: Vec<((i32, i32), f64, Option<f64>)> =
+            Vec::with_capacity(events.len() / 2);
+
+        for (t, n1, n2, kind) in events {
+            if kind == 'C' {
+                opened.entry((n1, n2)).or_insert_with(|| {
+                    intervals.push(((n1, n2), t, None));
+                    intervals.len() - 1
+                });
+            } else {
+                match opened.remove(&(n1, n2)) {
+                    Some(interval_id) => intervals[interval_id].2 = Some(t),
+                    None if t > t_first => intervals.push(((n1, n2), t_first, Some(t))),
+                    None => {}
+                }
+            }
+        }
+
+        let contacts = intervals
+            .into_iter()
+            .map(|(couple, start, end)| {
+                let start = Self::sample(start, t_first, time_step);
+                let end = match end {
+                    // The contact stops at the sample before the one of the suppression
+                    Some(end) => ((end - t_first) / time_step - 1e-9).ceil() as i32 - 1,
+                    None => Self::sample(t_last, t_first, time_step),
+                };
+
+                Contact {
+                    couple,
+                    start,
+                    end: end.max(start),
+                }
+            })
+            .collect();
+
+        Ok((
+            Self::from_contacts(contacts, interner, t_first, time_step),
+            skipped,
+        ))
+    }
+
+    /// Read a graph following the `GraphFileFormat::Binary` format, as written by `to_binary`
+    fn from_binary<R: Read>(mut reader: R) -> Result<Graph, GraphParseError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != BINARY_MAGIC {
+            return Err(GraphParseError::BadBinary(String::from(
+                "missing graphia header",
+            )));
+        }
+
+        let version = u32::from_le_bytes(Self::read_bytes(&mut reader)?);
+        if version == 0 || version > BINARY_VERSION {
+            return Err(GraphParseError::BadBinary(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+
+        let n_nodes = u32::from_le_bytes(Self::read_bytes(&mut reader)?);
+        let nodes: Vec<i32> = Self::read_block(&mut reader, 4 * n_nodes as u64, "node list")?
+            .chunks_exact(4)
+            .map(|node| i32::from_le_bytes([node[0], node[1], node[2], node[3]]))
+            .collect();
+
+        let n_labels = u32::from_le_bytes(Self::read_bytes(&mut reader)?) as usize;
+        let mut labels: Vec<String> = Vec::with_capacity(n_labels.min(BINARY_MAX_CAPACITY));
+        for _ in 0..n_labels {
+            let length = u32::from_le_bytes(Self::read_bytes(&mut reader)?);
+            let label = Self::read_block(&mut reader, length as u64, "node label")?;
+
+            labels.push(String::from_utf8(label).map_err(|_| {
+                GraphParseError::BadBinary(String::from("node label is not valid UTF-8"))
+            })?);
+        }
+
+        let duration = i32::from_le_bytes(Self::read_bytes(&mut reader)?);
+        let epoch = f64::from_le_bytes(Self::read_bytes(&mut reader)?);
+        let time_step = f64::from_le_bytes(Self::read_bytes(&mut reader)?);
+
+        // The seed has been added in the version 2
+        let seed = if version >= 2 {
+            let [has_seed] = Self::read_bytes::<_, 1>(&mut reader)?;
+            let seed = u64::from_le_bytes(Self::read_bytes(&mut reader)?);
+
+            if has_seed == 1 {
+                Some(seed)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        let n_contacts = u64::from_le_bytes(Self::read_bytes(&mut reader)?);
+        let length = n_contacts.checked_mul(16).ok_or_else(|| {
+            GraphParseError::BadBinary(format!("too many contacts: {}", n_contacts))
+        })?;
+        let packed = Self::read_block(&mut reader, length, "contact list")?;
+
+        let field = |contact: &[u8], i: usize| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&contact[4 * i..4 * (i + 1)]);
+            i32::from_le_bytes(bytes)
+        };
+        let contacts = packed
+            .chunks_exact(16)
+            .map(|contact| Contact {
+                couple: (field(contact, 0), field(contact, 1)),
+                start: field(contact, 2),
+                end: field(contact, 3),
+            })
+            .collect();
+
+        Ok(Graph {
+            nodes,
+            contacts,
+            duration,
+            labels,
+            epoch,
+            time_step,
+            seed,
+        })
+    }
+
+    /// Read exactly `length` bytes announced by the file, allocating the memory as the bytes are
+    /// read rather than trusting `length`
+    fn read_block<R: Read>(
+        reader: &mut R,
+        length: u64,
+        what: &str,
+    ) -> Result<Vec<u8>, GraphParseError> {
+        let mut bytes = Vec::new();
+        reader.by_ref().take(length).read_to_end(&mut bytes)?;
+
+        if bytes.len() as u64 != length {
+            return Err(GraphParseError::BadBinary(format!("truncated {}", what)));
+        }
+
+        Ok(bytes)
+    }
+
+    /// Read exactly `N` bytes
+    fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], GraphParseError> {
+        let mut bytes = [0u8; N];
+        reader.read_exact(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    /// Read a graph following the `GraphFileFormat::Tij` format
+    ///
+    /// Sightings of a pair separated by at most `gap` missing samples of length `period` are merged
+    /// in a single contact. Times are then binned in samples of width `time_step` so that the
+    /// first sighting happens at 0.
+    fn from_tij<R: BufRead>(
+        reader: R,
+        period: i32,
+        gap: i32,
+        lenient: bool,
+        time_step: f64,
+    ) -> Result<(Graph, usize), GraphParseError> {
+        let mut interner = NodeInterner::new();
+
+        let (mut sightings, skipped) = Self::parse_lines(reader, lenient, |line, text| {
+            let columns = Self::split_columns(line, text, 3)?;
+            let t = Self::parse_time(line, text, columns[0])?;
+            let (n1, n2) = Self::couple(&mut interner, columns[1], columns[2]);
+
+            Ok((t, n1, n2))
+        })?;
+        sightings.sort_by(|s1, s2| s1.0.partial_cmp(&s2.0).unwrap());
+
+        let t_first = sightings.first().map_or(0.0, |s| s.0);
+        let max_delay = (period * (gap + 1)) as f64;
+
+        // Index in `intervals` of the last interval (couple, first sighting, last sighting) of
+        // each couple
+        let mut last_interval: HashMap<(i32, i32), usize> = HashMap::new();
+        let mut intervals: Vec<((i32, i32), f64, f64)> = Vec::new();
+
+        for (t, n1, n2) in sightings {
+            match last_interval.get(&(n1, n2)) {
+                Some(&interval_id) if t - intervals[interval_id].2 <= max_delay => {
+                    intervals[interval_id].2 = t;
+                }
+                _ => {
+                    intervals.push(((n1, n2), t, t));
+                    last_interval.insert((n1, n2), intervals.len() - 1);
+                }
+            }
+        }
+
+        // Convert times into samples starting at 0
+        let contacts = intervals
+            .into_iter()
+            .map(|(couple, start, end)| Contact {
+                couple,
+                start: Self::sample(start, t_first, time_step),
+                end: Self::sample(end, t_first, time_step),
+            })
+            .collect();
+
+        Ok((
+            Self::from_contacts(contacts, interner, t_first, time_step),
+            skipped,
+        ))
+    }
+
+    /// Convert to `String` following the `GraphFileFormat::Tij` format
+    ///
+    /// A sighting is written for every sample of every contact, so the sampling period of the file
+    /// is the time step of the graph.
+    fn to_tij(&self) -> String {
+        let mut sightings: Vec<(i32, i32, i32)> = self
+            .contacts
+            .iter()
+            .flat_map(|c| {
+                let (n1, n2) = self.ordered_couple(c.couple);
+                (c.start..=c.end).map(move |t| (t, n1, n2))
+            })
+            .collect();
+
+        sightings.sort_by_key(|s| s.0);
+        self.comment_header()
+            + &sightings
+                .into_iter()
+                .map(|s| {
+                    format!(
+                        "{} {} {}\n",
+                        self.time(s.0),
+                        self.label(s.1),
+                        self.label(s.2)
+                    )
+                })
+                .collect::<String>()
+    }
+
+    /// Convert to `String` following the `GraphFileFormat::CreateDelete` format
     ///
     /// The algorithm has a time complexity of O(nlog(n)) where n is the number
     /// of contact
@@ -142,14 +1004,193 @@ impl Graph {
         let mut events: Vec<(i32, i32, i32, char)> = Vec::with_capacity(self.contacts.len());
 
         for contact in &self.contacts {
-            events.push((contact.start, contact.couple.0, contact.couple.1, 'C'));
-            events.push((contact.end + 1, contact.couple.0, contact.couple.1, 'S'));
+            let (n1, n2) = self.ordered_couple(contact.couple);
+            events.push((contact.start, n1, n2, 'C'));
+            events.push((contact.end + 1, n1, n2, 'S'));
         }
-        events.sort_by(|e1, e2| e1.0.cmp(&e2.0));
-        events
-            .into_iter()
-            .map(|e| format!("{} {} {} {}\n", e.0, e.1, e.2, e.3))
-            .collect()
+        events.sort_by_key(|e1| e1.0);
+        self.comment_header()
+            + &events
+                .into_iter()
+                .map(|e| {
+                    format!(
+                        "{} {} {} {}\n",
+                        self.time(e.0),
+                        self.label(e.1),
+                        self.label(e.2),
+                        e.3
+                    )
+                })
+                .collect::<String>()
+    }
+
+    /// Convert to bytes following the `GraphFileFormat::Binary` format
+    ///
+    /// All numbers are little endian. The layout is:
+    /// * the magic bytes `GRAPHIA\0` and the format version (`u32`)
+    /// * the number of nodes (`u32`) followed by the nodes (`i32`)
+    /// * the number of labels (`u32`) followed by each label, as its length in bytes (`u32`) and
+    ///   its UTF-8 bytes
+    /// * the duration (`i32`), the epoch (`f64`) and the time step (`f64`)
+    /// * whether the graph has a seed (`u8`, 0 or 1) and the seed (`u64`, 0 if there is none)
+    /// * the number of contacts (`u64`) followed by the contacts, packed as `n1 n2 start end`
+    ///   (`i32` each)
+    fn to_binary(&self) -> Vec<u8> {
+        let labels_length: usize = self.labels.iter().map(|l| 4 + l.len()).sum();
+        let mut bytes: Vec<u8> = Vec::with_capacity(
+            57 + 4 * self.nodes.len() + labels_length + 16 * self.contacts.len(),
+        );
+
+        bytes.extend_from_slice(BINARY_MAGIC);
+        bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+
+        bytes.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        for node in &self.nodes {
+            bytes.extend_from_slice(&node.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.labels.len() as u32).to_le_bytes());
+        for label in &self.labels {
+            bytes.extend_from_slice(&(label.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(label.as_bytes());
+        }
+
+        bytes.extend_from_slice(&self.duration.to_le_bytes());
+        bytes.extend_from_slice(&self.epoch.to_le_bytes());
+        bytes.extend_from_slice(&self.time_step.to_le_bytes());
+
+        bytes.push(self.seed.is_some() as u8);
+        bytes.extend_from_slice(&self.seed.unwrap_or(0).to_le_bytes());
+
+        bytes.extend_from_slice(&(self.contacts.len() as u64).to_le_bytes());
+        for contact in &self.contacts {
+            bytes.extend_from_slice(&contact.couple.0.to_le_bytes());
+            bytes.extend_from_slice(&contact.couple.1.to_le_bytes());
+            bytes.extend_from_slice(&contact.start.to_le_bytes());
+            bytes.extend_from_slice(&contact.end.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Metadata written as comments at the top of the text formats, ignored when reading them
+    fn comment_header(&self) -> String {
+        match self.seed {
+            Some(seed) => format!("# seed: {}\n", seed),
+            None => String::new(),
+        }
+    }
+
+    /// Escape the XML special characters of a label
+    fn escape_xml(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&apos;")
+    }
+
+    /// Convert to `String` following the `GraphFileFormat::Gexf` format
+    ///
+    /// The contacts of each pair of nodes are gathered in a single edge, each contact being a
+    /// spell of this edge. Times are the absolute times of the original trace.
+    fn to_gexf(&self) -> String {
+        let mut pairs: HashMap<(i32, i32), Vec<&Contact>> = HashMap::new();
+        for contact in &self.contacts {
+            pairs.entry(contact.couple).or_default().push(contact);
+        }
+
+        let mut couples: Vec<&(i32, i32)> = pairs.keys().collect();
+        couples.sort();
+
+        let mut text = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <gexf xmlns=\"http://www.gexf.net/1.2draft\" version=\"1.2\">\n",
+        );
+
+        // The seed goes in the metadata, as GEXF attributes only apply to nodes and edges
+        if let Some(seed) = self.seed {
+            text.push_str(&format!(
+                "  <meta>\n    <description>seed: {}</description>\n  </meta>\n",
+                seed
+            ));
+        }
+        text.push_str(
+            "  <graph mode=\"dynamic\" defaultedgetype=\"undirected\" timeformat=\"double\">\n\
+             \x20   <nodes>\n",
+        );
+
+        for node in &self.nodes {
+            text.push_str(&format!(
+                "      <node id=\"{}\" label=\"{}\"/>\n",
+                node,
+                Self::escape_xml(&self.label(*node))
+            ));
+        }
+        text.push_str("    </nodes>\n    <edges>\n");
+
+        for (edge_id, couple) in couples.into_iter().enumerate() {
+            let (source, target) = self.ordered_couple(*couple);
+            text.push_str(&format!(
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\">\n        <spells>\n",
+                edge_id, source, target
+            ));
+
+            for contact in &pairs[couple] {
+                text.push_str(&format!(
+                    "          <spell start=\"{}\" end=\"{}\"/>\n",
+                    self.time(contact.start),
+                    self.time(contact.end)
+                ));
+            }
+            text.push_str("        </spells>\n      </edge>\n");
+        }
+        text.push_str("    </edges>\n  </graph>\n</gexf>\n");
+
+        text
+    }
+
+    /// Convert to `String` following the `GraphFileFormat::GraphMl` format
+    ///
+    /// GraphML has no notion of time, so each contact is an edge with its `start` and `end` times
+    /// as attributes. Times are the absolute times of the original trace.
+    fn to_graphml(&self) -> String {
+        let mut text = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+             \x20 <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"start\" for=\"edge\" attr.name=\"start\" attr.type=\"double\"/>\n\
+             \x20 <key id=\"end\" for=\"edge\" attr.name=\"end\" attr.type=\"double\"/>\n\
+             \x20 <graph id=\"G\" edgedefault=\"undirected\">\n",
+        );
+
+        if let Some(seed) = self.seed {
+            text.push_str(&format!("    <desc>seed: {}</desc>\n", seed));
+        }
+
+        for node in &self.nodes {
+            text.push_str(&format!(
+                "    <node id=\"n{}\"><data key=\"label\">{}</data></node>\n",
+                node,
+                Self::escape_xml(&self.label(*node))
+            ));
+        }
+
+        for (edge_id, contact) in self.contacts.iter().enumerate() {
+            let (source, target) = self.ordered_couple(contact.couple);
+            text.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">\
+                 <data key=\"start\">{}</data><data key=\"end\">{}</data></edge>\n",
+                edge_id,
+                source,
+                target,
+                self.time(contact.start),
+                self.time(contact.end)
+            ));
+        }
+        text.push_str("  </graph>\n</graphml>\n");
+
+        text
     }
 
     /// Save the graph to a file
@@ -163,6 +1204,26 @@ impl Graph {
                 let text = self.to_create_delete();
                 fs::write(filename, text)?;
             }
+            GraphFileFormat::Tij { .. } => {
+                let text = self.to_tij();
+                fs::write(filename, text)?;
+            }
+            GraphFileFormat::Gexf => {
+                let text = self.to_gexf();
+                fs::write(filename, text)?;
+            }
+            GraphFileFormat::GraphMl => {
+                let text = self.to_graphml();
+                fs::write(filename, text)?;
+            }
+            GraphFileFormat::Binary => {
+                fs::write(filename, self.to_binary())?;
+            }
+            #[cfg(feature = "json")]
+            GraphFileFormat::Json => {
+                let text = serde_json::to_string(self)?;
+                fs::write(filename, text)?;
+            }
         };
 
         Ok(())
@@ -183,7 +1244,7 @@ impl Graph {
                 return c.start - contact.end;
             }
         }
-        return -1;
+        -1
     }
 
     /// Calculates the inter_contact histogram over the graph
@@ -193,7 +1254,7 @@ impl Graph {
         let mut inter_contact = Vec::with_capacity(self.contacts.len());
 
         for i in 0..self.contacts.len() {
-            inter_contact.push(self.inter_contact(i as usize));
+            inter_contact.push(self.inter_contact(i));
         }
 
         for x in inter_contact {
@@ -218,7 +1279,7 @@ impl Graph {
             events.push((contact.end + 1, contact.couple.0, contact.couple.1, 'S'));
         }
 
-        events.sort_by(|e1, e2| e1.0.cmp(&e2.0));
+        events.sort_by_key(|e1| e1.0);
         let mut avg_degrees: Vec<f32> = Vec::with_capacity(self.duration as usize);
 
         let mut avg_degree: f32 = 0.0;
@@ -254,7 +1315,7 @@ impl Graph {
             events.push((contact.end + 1, contact.couple.0, contact.couple.1, 'S'));
         }
 
-        events.sort_by(|e1, e2| e1.0.cmp(&e2.0));
+        events.sort_by_key(|e1| e1.0);
         let mut fraction_created: Vec<f32> = Vec::with_capacity(self.duration as usize);
 
         let n: f32 = self.nodes.len() as f32;
@@ -294,7 +1355,7 @@ impl Graph {
             events.push((contact.end + 1, contact.couple.0, contact.couple.1, 'S'));
         }
 
-        events.sort_by(|e1, e2| e1.0.cmp(&e2.0));
+        events.sort_by_key(|e1| e1.0);
         let mut fraction_deleted: Vec<f32> = Vec::with_capacity(self.duration as usize);
         fraction_deleted.push(-1.0);
 
@@ -309,7 +1370,7 @@ impl Graph {
                     -1 => {
                         n_links = 0;
                         -1.0
-                    },
+                    }
                     0 => 0.0,
                     _ => (deleted_edges as f32) / (n_links as f32),
                 });
@@ -330,8 +1391,106 @@ impl Graph {
         fraction_deleted
     }
 
+    /// Time intervals during which each pair of nodes is linked, sorted by pair and start time
+    ///
+    /// A contact links its pair in the snapshots `start` to `end`, the contacts of a pair that
+    /// overlap or follow each other are merged.
+    pub fn link_intervals(&self) -> Vec<Contact> {
+        let mut contacts = self.contacts.clone();
+        contacts.sort_by_key(|c| (c.couple, c.start));
+
+        let mut intervals: Vec<Contact> = Vec::with_capacity(contacts.len());
+        for contact in contacts {
+            match intervals.last_mut() {
+                Some(last) if last.couple == contact.couple && contact.start <= last.end + 1 => {
+                    last.end = last.end.max(contact.end);
+                }
+                _ => intervals.push(contact),
+            }
+        }
+
+        intervals
+    }
+
+    /// Count the transitions of the pairs of nodes between consecutive snapshots
+    ///
+    /// The element `t` counts the transitions from the snapshot `t - 1` to the snapshot `t`, for
+    /// `t` from 1 to `duration`. The element 0 is empty.
+    pub fn transitions(&self) -> Vec<Transitions> {
+        let duration = self.duration.max(0) as usize;
+        let n = self.nodes.len() as u64;
+        let n_pairs = n * n.saturating_sub(1) / 2;
+
+        let mut transitions = vec![Transitions::default(); duration + 1];
+        // Variation of the number of links from a snapshot to the next one
+        let mut delta_links = vec![0i64; duration + 2];
+
+        for interval in self.link_intervals() {
+            if interval.end < 0 || interval.start > self.duration {
+                continue;
+            }
+            let start = interval.start.max(0) as usize;
+            let end = interval.end.min(self.duration) as usize;
+
+            if start >= 1 {
+                transitions[start].created += 1;
+            }
+            if end < duration {
+                transitions[end + 1].deleted += 1;
+            }
+
+            delta_links[start] += 1;
+            delta_links[end + 1] -= 1;
+        }
+
+        let mut links: i64 = 0;
+        for t in 1..=duration {
+            links += delta_links[t - 1];
+
+            transitions[t].links = links as u64;
+            transitions[t].non_links = n_pairs.saturating_sub(links as u64);
+        }
+
+        transitions
+    }
+
+    /// Count the transitions of each pair of nodes linked at least once, over the whole graph
+    ///
+    /// The pairs are sorted and the transitions are counted as in `Graph::transitions`.
+    pub fn pair_transitions(&self) -> Vec<((i32, i32), Transitions)> {
+        let duration = self.duration.max(0) as u64;
+        let mut pairs: Vec<((i32, i32), Transitions)> = Vec::new();
+
+        for interval in self.link_intervals() {
+            if interval.end < 0 || interval.start > self.duration {
+                continue;
+            }
+
+            if pairs.last().map(|p| p.0) != Some(interval.couple) {
+                pairs.push((interval.couple, Transitions::default()));
+            }
+            let transitions = &mut pairs.last_mut().unwrap().1;
+
+            let start = interval.start.max(0);
+            if start >= 1 {
+                transitions.created += 1;
+            }
+            if interval.end < self.duration {
+                transitions.deleted += 1;
+            }
+            // Steps t from 1 to duration where the pair is linked at t - 1
+            transitions.links += (interval.end.min(self.duration - 1) - start + 1).max(0) as u64;
+        }
+
+        for (_, transitions) in &mut pairs {
+            transitions.non_links = duration - transitions.links;
+        }
+
+        pairs
+    }
+
     /// Calculates total duration of the graph observation
     fn update_duration(&mut self) {
-        self.duration = self.contacts.iter().map(|c| c.end).max().unwrap()
+        self.duration = self.contacts.iter().map(|c| c.end).max().unwrap_or(0)
     }
 }
@@ -1,15 +1,20 @@
 use std::io::Error;
+use std::str::FromStr;
 
 use gnuplot::{Color, Figure, AxesCommon};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
-use log::{info, debug};
+use log::{info, debug, warn, error};
 
 mod graph;
-use graph::Graph;
+use graph::{Graph, GraphFileFormat, ReadOptions};
 
 mod models;
+use models::{DurationLawKind, FitOptions, InitialState, InitialStateKind, ModelKind, TemporalModel};
+
+mod null_models;
+use null_models::NullModel;
 
 /// Dynamic graphs analysis and simulation.
 #[derive(Debug, StructOpt)]
@@ -23,6 +28,10 @@ struct Opt {
     #[structopt(short = "n", long)]
     no_show: bool,
 
+    /// Skip and count malformed lines of the input files instead of stopping at the first one
+    #[structopt(short, long)]
+    lenient: bool,
+
     /// Where to truncate the inter-contacts histogram
     #[structopt(short, long, default_value = "0.01")]
     truncate: f32,
@@ -31,21 +40,94 @@ struct Opt {
     cmd: Command,
 }
 
+/// Graph input file and the way to read it
+#[derive(Debug, StructOpt)]
+struct GraphInput {
+    /// Graph input file
+    ///
+    /// The file should be formatted as such : <n1 n2 ts te> where n1 and n2
+    /// are the identifiers of the two nodes involved in the
+    /// contact,  n1 < n2, ts stands for the time at which the contact started, and te the
+    /// time at which the last contact between n1 and n2 has been recorded. Use `-` to read
+    /// the standard input, files ending with `.gz` are decompressed. Times can be integers, Unix
+    /// timestamps (with fractional seconds) or ISO-8601 dates.
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+
+    /// Format of the input file: `start-end`, `create-delete`, `tij`, `binary` or `json`
+    #[structopt(short, long, alias = "from", default_value = "start-end")]
+    format: GraphFileFormat,
+
+    /// Sampling period of a `tij` file, in the time unit of the file
+    #[structopt(long, default_value = "20")]
+    period: i32,
+
+    /// Number of missing samples tolerated inside a contact of a `tij` file
+    #[structopt(long, default_value = "0")]
+    gap: i32,
+
+    /// Width of a time sample used for the analysis, in the time unit of the file (seconds for
+    /// dates), e.g. `20`, `5min` or `1h`. Defaults to 1, or to the period of a `tij` file
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    time_step: Option<f64>,
+}
+
+/// Options used to fit the models on a graph
+#[derive(Debug, StructOpt)]
+struct FitArgs {
+    /// Period of the periodic model, in the time unit of the file (e.g. `24h`). Chosen between a
+    /// day, a week and no period by default
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    model_period: Option<f64>,
+
+    /// Law of the contact and inter-contact durations of the semi-Markov model: `empirical`,
+    /// `geometric` or `power-law`. Defaults to `empirical`
+    #[structopt(long)]
+    duration_law: Option<DurationLawKind>,
+
+    /// Communities of the nodes for the community model, in a file where each line `label
+    /// community` gives the community of a node. Inferred from the contacts by default
+    #[structopt(long, parse(from_os_str))]
+    communities: Option<PathBuf>,
+}
+
+/// Models that can be simulated without a dataset
+#[derive(Debug, Clone, Copy)]
+enum SimulationModel {
+    EdgeMarkovian,
+    RandomWaypoint,
+}
+
+impl FromStr for SimulationModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "edge-markovian" => Ok(SimulationModel::EdgeMarkovian),
+            "random-waypoint" => Ok(SimulationModel::RandomWaypoint),
+            _ => Err(format!("unknown model: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 enum Command {
     /// Analyse a given graph and display its main characteristics
     Analyse {
-        /// Graph input file
-        ///
-        /// The file should be formatted as such : <n1 n2 ts te> where n1 and n2
-        /// are the identifiers of the two nodes involved in the
-        /// contact,  n1 < n2, ts stands for the time at which the contact started, and te the
-        /// time at which the last contact between n1 and n2 has been recorded.
-        #[structopt(parse(from_os_str))]
-        file: PathBuf,
+        #[structopt(flatten)]
+        input: GraphInput,
+
+        /// Also write the graph to this file
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Format of the output file: `start-end`, `create-delete`, `tij`, `gexf`, `graphml`,
+        /// `binary` or `json`
+        #[structopt(long, default_value = "start-end")]
+        output_format: GraphFileFormat,
     },
 
-    /// Generate a graph using an EdgeMarkovian model
+    /// Generate a graph using an EdgeMarkovian or a random waypoint model
     Simulate {
         /// Number of time steps to generate
         #[structopt(short = "D", long)]
@@ -55,13 +137,67 @@ enum Command {
         #[structopt(short, long)]
         n_nodes: i32,
 
+        /// Model to simulate: `edge-markovian` or `random-waypoint` (nodes moving in an area,
+        /// linked when in range of each other)
+        #[structopt(long, default_value = "edge-markovian")]
+        model: SimulationModel,
+
         /// Creation probability
-        #[structopt(short = "cp", long)]
-        creation_probability: f32,
+        #[structopt(short = "cp", long, required_if("model", "edge-markovian"))]
+        creation_probability: Option<f32>,
 
         /// Deletion probability
-        #[structopt(short = "dp", long)]
-        deletion_probability: f32,
+        #[structopt(short = "dp", long, required_if("model", "edge-markovian"))]
+        deletion_probability: Option<f32>,
+
+        /// Width of the area of the random waypoint model
+        #[structopt(long, default_value = "1000")]
+        width: f32,
+
+        /// Height of the area of the random waypoint model
+        #[structopt(long, default_value = "1000")]
+        height: f32,
+
+        /// Minimum speed of the nodes of the random waypoint model, in distance per time step
+        #[structopt(long, default_value = "1")]
+        min_speed: f32,
+
+        /// Maximum speed of the nodes of the random waypoint model, in distance per time step
+        #[structopt(long, default_value = "10")]
+        max_speed: f32,
+
+        /// Number of time steps the nodes of the random waypoint model wait at each waypoint
+        #[structopt(long, default_value = "0")]
+        pause_time: i32,
+
+        /// Distance under which two nodes of the random waypoint model are linked
+        #[structopt(long, default_value = "50")]
+        range: f32,
+
+        /// Write the simulated graph to this file
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Format of the output file: `start-end`, `create-delete`, `tij`, `gexf`, `graphml`,
+        /// `binary` or `json`
+        #[structopt(short, long, default_value = "start-end")]
+        format: GraphFileFormat,
+
+        /// Seed of the random generator, to repeat a simulation. Drawn at random if not given
+        #[structopt(long)]
+        seed: Option<u64>,
+
+        /// Pairs linked at the first time step of the Edge-Markovian model: `empty`, `stationary`
+        /// (each pair linked with probability p / (p + d)) or `first-snapshot` (the pairs linked at
+        /// the first time step of `--reference`)
+        #[structopt(long, default_value = "empty")]
+        initial_state: InitialStateKind,
+
+        /// Graph whose first snapshot is copied by `--initial-state first-snapshot`, in the
+        /// `start-end` format. Its nodes are numbered from 1 in their order of appearance in the
+        /// file
+        #[structopt(long, parse(from_os_str), required_if("initial-state", "first-snapshot"))]
+        reference: Option<PathBuf>,
     },
 
     /// Analyse a graph and compare it to it's modeled version using Edge-Markovian model
@@ -69,24 +205,149 @@ enum Command {
         /// Model to simulate and compare with the data
         ///
         /// Can be :
-        /// * `1`: Edge Markovian model
-        /// * `2`: Time Dependent Edge Markovian model
-        /// * `3`: Time Dependent Edge Markovian model with delayed nodes
+        /// * `1` or `edge-markovian`: Edge Markovian model
+        /// * `2` or `time-dependent-edge-markovian`: Time Dependent Edge Markovian model
+        /// * `3` or `semi-markov`: Semi-Markov model, with contact and inter-contact durations
+        ///   following arbitrary laws
+        /// * `4` or `heterogeneous-edge-markovian`: Edge Markovian model with probabilities
+        ///   specific to each pair of nodes
+        /// * `5` or `activity-driven`: Activity-driven model
+        /// * `6` or `periodic-edge-markovian`: Edge Markovian model with periodic probabilities
+        /// * `7` or `community-edge-markovian`: Edge Markovian model with probabilities depending
+        ///   on the communities of the nodes
         #[structopt(long_help = "Can be : \n \
-            \t * 1: Edge Markovian model \n \
-            \t * 2: Time Dependent Edge Markovian model \n \
-            \t * 3: Time Dependent Edge Markovian model with delayed nodes (unstable, does not work properly")]
-        model: u8,
+            \t * 1 or edge-markovian: Edge Markovian model \n \
+            \t * 2 or time-dependent-edge-markovian: Time Dependent Edge Markovian model \n \
+            \t * 3 or semi-markov: Semi-Markov model, with contact and inter-contact durations following arbitrary laws \n \
+            \t * 4 or heterogeneous-edge-markovian: Edge Markovian model with probabilities specific to each pair of nodes \n \
+            \t * 5 or activity-driven: Activity-driven model \n \
+            \t * 6 or periodic-edge-markovian: Edge Markovian model with periodic probabilities \n \
+            \t * 7 or community-edge-markovian: Edge Markovian model with probabilities depending on the communities of the nodes")]
+        model: ModelKind,
+
+        #[structopt(flatten)]
+        input: GraphInput,
+
+        #[structopt(flatten)]
+        fit: FitArgs,
+
+        /// Duration of the simulated graph for the periodic model, in the time unit of the file
+        /// (e.g. `7d`). Defaults to the duration of the input graph
+        #[structopt(long, parse(try_from_str = parse_duration))]
+        model_duration: Option<f64>,
+
+        /// Pairs linked at the first time step of the simulated graph for the Edge-Markovian
+        /// models: `empty`, `stationary` (each pair linked with probability p / (p + d)) or
+        /// `first-snapshot` (the pairs linked at the first time step of the input graph). Defaults
+        /// to `empty`
+        #[structopt(long)]
+        initial_state: Option<InitialStateKind>,
+
+        /// Save the fitted model parameters as JSON, to regenerate graphs from them later
+        #[cfg(feature = "json")]
+        #[structopt(long, parse(from_os_str))]
+        save_model: Option<PathBuf>,
+
+        /// Seed of the random generator, to repeat a simulation. Drawn at random if not given
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+
+    /// Fit models on a graph and rank them by their Akaike information criterion
+    Score {
+        #[structopt(flatten)]
+        input: GraphInput,
+
+        /// Model to score, by name or number (see `compare`), repeated for several models. Defaults
+        /// to every model
+        #[structopt(short, long, number_of_values = 1)]
+        models: Vec<ModelKind>,
+
+        #[structopt(flatten)]
+        fit: FitArgs,
+    },
 
-        /// Graph input file
+    /// Convert a graph file to an other format, e.g. `binary` for fast reloading
+    ///
+    /// The input format is given by `--from` (or `--format`) and the time can be rebinned with
+    /// `--time-step`.
+    Convert {
+        #[structopt(flatten)]
+        input: GraphInput,
+
+        /// Output file
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+
+        /// Format of the output file: `start-end`, `create-delete`, `tij`, `gexf`, `graphml`,
+        /// `binary` or `json`
+        #[structopt(long, default_value = "binary")]
+        to: GraphFileFormat,
+
+        /// Shift the times so that the first sample happens at this time, e.g. `0` to hide the
+        /// dates of the trace
+        #[structopt(long, allow_hyphen_values = true)]
+        epoch: Option<f64>,
+
+        /// Rename the nodes following a file where each line `old new` gives the new label of a
+        /// node
+        #[structopt(long, parse(from_os_str))]
+        relabel: Option<PathBuf>,
+
+        /// Rename the nodes from 1 to n, hiding their original identifiers
+        #[structopt(long, conflicts_with = "relabel")]
+        anonymise: bool,
+    },
+
+    /// Randomise a graph with a null model and compare it to the original graph, to tell whether
+    /// its properties are significant
+    Randomise {
+        /// Null model used to randomise the graph
         ///
-        /// The file should be formatted as such : <n1 n2 ts te> where n1 and n2
-        /// are the identifiers of the two nodes involved in the
-        /// contact,  n1 < n2, ts stands for the time at which the contact started, and te the
-        /// time at which the last contact between n1 and n2 has been recorded.
+        /// Can be :
+        /// * `start-times`: shuffle the start times of the contacts
+        /// * `inter-contacts`: shuffle the inter-contact durations of each pair
+        /// * `pairs`: move the contacts of each pair to a random pair, keeping their timings
+        /// * `snapshots`: shuffle the order of the snapshots
+        null_model: NullModel,
+
+        #[structopt(flatten)]
+        input: GraphInput,
+
+        /// Also write the randomised graph to this file
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Format of the output file: `start-end`, `create-delete`, `tij`, `gexf`, `graphml`,
+        /// `binary` or `json`
+        #[structopt(long, default_value = "start-end")]
+        output_format: GraphFileFormat,
+
+        /// Seed of the random generator, to repeat a randomisation. Drawn at random if not given
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+
+    /// Generate a graph from a model saved by `compare --save-model`
+    #[cfg(feature = "json")]
+    Regenerate {
+        /// Model file written by `compare --save-model`
         #[structopt(parse(from_os_str))]
-        file: PathBuf,
-    }
+        model_file: PathBuf,
+
+        /// Write the generated graph to this file
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Format of the output file: `start-end`, `create-delete`, `tij`, `gexf`, `graphml`,
+        /// `binary` or `json`
+        #[structopt(short, long, default_value = "start-end")]
+        format: GraphFileFormat,
+
+        /// Seed of the random generator, to repeat a simulation. Drawn at random if not given
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
 }
 
 
@@ -97,96 +358,200 @@ fn main() -> Result<(), Error> {
     std::env::set_var("RUST_LOG", "TRACE");
     pretty_env_logger::init();
 
-    let truncate = opt.truncate;
-
     // let (mut histo_fig, mut frac_fig, mut degree_fig) = match opt.cmd {
     let mut figures: Vec<Figure> = match opt.cmd {
-        Command::Analyse { file } => {
-            let analyse = Graph::from_file(file.to_str().unwrap())?;
+        Command::Analyse { input, output, output_format } => {
+            let analyse = load_graph(&input, opt.lenient);
+
+            if let Some(output) = output {
+                analyse.save(output.to_str().unwrap(), output_format)?;
+            }
 
             analyse_graph(&analyse, "", opt.truncate)
         },
-        Command::Simulate { duration, n_nodes, creation_probability, deletion_probability } => {
-            let simulation: Graph = Graph::from(models::EdgeMarkovian {
-                duration,
-                number_of_nodes: n_nodes,
-                creation_probability,
-                deletion_probability,
-            });
+        Command::Simulate {
+            duration,
+            n_nodes,
+            model,
+            creation_probability,
+            deletion_probability,
+            width,
+            height,
+            min_speed,
+            max_speed,
+            pause_time,
+            range,
+            output,
+            format,
+            seed,
+            initial_state,
+            reference,
+        } => {
+            let seed = choose_seed(seed);
+            let mut rng = models::seeded_rng(seed);
+
+            let mut simulation: Graph = match model {
+                SimulationModel::EdgeMarkovian => {
+                    let initial_state = match reference {
+                        Some(reference) => {
+                            let filename = reference.to_str().unwrap();
+                            let reference = Graph::from_file(filename).unwrap_or_else(|e| {
+                                error!("could not read {}: {}", filename, e);
+                                std::process::exit(1);
+                            });
+
+                            InitialState::new(initial_state, &reference)
+                        }
+                        None => InitialState::new(initial_state, &Graph::default()),
+                    };
+
+                    models::EdgeMarkovian {
+                        duration,
+                        number_of_nodes: n_nodes,
+                        // Required by the command line parser for this model
+                        creation_probability: creation_probability.unwrap(),
+                        deletion_probability: deletion_probability.unwrap(),
+                        initial_state,
+                    }.generate(&mut rng)
+                }
+                SimulationModel::RandomWaypoint => models::RandomWaypoint {
+                    width,
+                    height,
+                    min_speed,
+                    max_speed,
+                    pause_time,
+                    range,
+                    duration,
+                    number_of_nodes: n_nodes,
+                }.generate(&mut rng),
+            };
+            simulation.seed = Some(seed);
+
+            if let Some(output) = output {
+                simulation.save(output.to_str().unwrap(), format)?;
+            }
 
             analyse_graph(&simulation, "", opt.truncate)
         },
-        Command::Compare { model, file } => {
-            debug!("Analysing graph");
-            let analyse: Graph = Graph::from_file(file.to_str().unwrap())?;
+        Command::Score { input, models, fit } => {
+            let graph = load_graph(&input, opt.lenient);
+
+            let models = if models.is_empty() {
+                ModelKind::ALL.to_vec()
+            } else {
+                models
+            };
+
+            let options = fit_options(&graph, &fit, None);
+            let mut scores = Vec::with_capacity(models.len());
+            for kind in models {
+                let fitted = kind.fit(&graph, &options);
+                let model = fitted.model();
+
+                match model.score(&graph) {
+                    Some(score) => scores.push((model.name(), score)),
+                    None => warn!("{}: the likelihood cannot be computed", model.name()),
+                }
+            }
+
+            scores.sort_by(|a, b| a.1.aic.partial_cmp(&b.1.aic).unwrap_or(std::cmp::Ordering::Equal));
+            for (rank, (name, score)) in scores.iter().enumerate() {
+                info!(
+                    "{}. {}: log-likelihood {}, AIC {}, BIC {}",
+                    rank + 1, name, score.log_likelihood, score.aic, score.bic
+                );
+            }
+
+            vec![]
+        },
+        Command::Convert { input, output, to, epoch, relabel, anonymise } => {
+            let mut graph = load_graph(&input, opt.lenient);
+
+            if let Some(epoch) = epoch {
+                graph.epoch = epoch;
+            }
 
-            let frac_created = analyse.fraction_created_links();
-            let frac_deleted = analyse.fraction_deleted_links();
+            if let Some(relabel) = relabel {
+                let filename = relabel.to_str().unwrap();
+                let mapping = Graph::read_labels(filename).unwrap_or_else(|e| {
+                    error!("could not read {}: {}", filename, e);
+                    std::process::exit(1);
+                });
+
+                graph.relabel(&mapping);
+            } else if anonymise {
+                graph.anonymise_labels();
+            }
+
+            graph.save(output.to_str().unwrap(), to)?;
+
+            vec![]
+        },
+        Command::Randomise { null_model, input, output, output_format, seed } => {
+            let graph = load_graph(&input, opt.lenient);
+            let mut analyse_figs = analyse_graph(&graph, "REAL GRAPH: ", opt.truncate);
+
+            let seed = choose_seed(seed);
+            let mut randomised = null_model.randomise(&graph, &mut models::seeded_rng(seed));
+            randomised.seed = Some(seed);
+
+            if let Some(output) = output {
+                randomised.save(output.to_str().unwrap(), output_format)?;
+            }
+
+            info!("Analysing randomised graph");
+            let mut null_figs = analyse_graph(&randomised, "NULL MODEL: ", opt.truncate);
+            analyse_figs.append(&mut null_figs);
+
+            analyse_figs
+        },
+        #[cfg(feature = "json")]
+        Command::Regenerate { model_file, output, format, seed } => {
+            let fitted = models::FittedModel::from_file(model_file.to_str().unwrap())?;
+
+            let seed = choose_seed(seed);
+            let mut simulation = fitted.generate(&mut models::seeded_rng(seed));
+            simulation.seed = Some(seed);
+
+            if let Some(output) = output {
+                simulation.save(output.to_str().unwrap(), format)?;
+            }
+
+            analyse_graph(&simulation, "", opt.truncate)
+        },
+        Command::Compare {
+            model,
+            input,
+            fit,
+            model_duration,
+            initial_state,
+            #[cfg(feature = "json")]
+            save_model,
+            seed,
+        } => {
+            debug!("Analysing graph");
+            let analyse: Graph = load_graph(&input, opt.lenient);
 
             let mut analyse_figs = analyse_graph(&analyse, "REAL GRAPH: ", opt.truncate);
 
             debug!("Creating model (can take a very long time)");
-            let simulation: Graph;
-
-            match model {
-                1 => {
-                    // Compute Evolving-EdgeMarkovian model parameters
-                    let creation_probability = frac_created.iter().filter(|&x| x >= &0.0)
-                        .sum::<f32>() / frac_created.len() as f32;
-                    let deletion_probability = frac_deleted.iter().filter(|&x| x >= &0.0)
-                        .sum::<f32>() / frac_deleted.len() as f32;
-
-                    simulation = Graph::from(models::EdgeMarkovian {
-                        duration: analyse.duration,
-                        number_of_nodes: analyse.nodes.len() as i32,
-                        creation_probability,
-                        deletion_probability,
-                    });
-                },
-                2 => {
-                    // remove all "-1" in the data
-                    let creation_probability = frac_created.iter()
-                        .map(|&frac| 0f32.max(frac))
-                        .collect();
-                    let deletion_probability = frac_deleted.iter()
-                        .map(|&frac| 0f32.max(frac))
-                        .collect();
-
-                    simulation = Graph::from(models::TimeDependentEdgeMarkovian {
-                        duration: analyse.duration,
-                        number_of_nodes: analyse.nodes.len() as i32,
-                        creation_probability,
-                        deletion_probability,
-                    });
-                },
-                3 => {
-                    // remove all "-1" in the data
-                    let creation_probability = frac_created.iter()
-                        .map(|&frac| 0f32.max(frac))
-                        .collect();
-                    let deletion_probability = frac_deleted.iter()
-                        .map(|&frac| 0f32.max(frac))
-                        .collect();
-
-                    // Compute and truncate contacts histogram
-                    let mut contacts_histogram: Vec<i32> = analyse.inter_contact_histo();
-                    let max: f32 = *contacts_histogram.iter().max().unwrap_or(&0) as f32;
-
-                    contacts_histogram = contacts_histogram.into_iter()
-                        .filter(|&x| x >= (truncate * max) as i32)
-                        .collect();
-
-                    simulation = Graph::from(models::DelayedTimeDependentEdgeMarkovian {
-                        duration: analyse.duration,
-                        number_of_nodes: analyse.nodes.len() as i32,
-                        creation_probability,
-                        deletion_probability,
-                        intercontacts_histogram: contacts_histogram,
-                    });
+            let mut options = fit_options(&analyse, &fit, model_duration);
+            options.initial_state = initial_state;
+
+            let fitted = model.fit(&analyse, &options);
+            info!("model: {}", fitted.model().name());
+
+            #[cfg(feature = "json")]
+            {
+                if let Some(model_file) = save_model {
+                    fitted.save(model_file.to_str().unwrap())?;
                 }
-                _ => unimplemented!()
             }
 
+            let seed = choose_seed(seed);
+            let mut simulation = fitted.generate(&mut models::seeded_rng(seed));
+            simulation.seed = Some(seed);
+
             info!("Analysing model");
             let mut model_figs = analyse_graph(&simulation, "MODEL: ", opt.truncate);
             analyse_figs.append(&mut model_figs);
@@ -195,28 +560,25 @@ fn main() -> Result<(), Error> {
         }
     };
 
-    match opt.save {
-        Some(destination) => {
-            if destination.is_dir() == false {
-                std::fs::create_dir(&destination)?;
-            }
+    if let Some(destination) = opt.save {
+        if !destination.is_dir() {
+            std::fs::create_dir(&destination)?;
+        }
 
-            for (i, figure) in figures.iter_mut().enumerate() {
-                let mut path = PathBuf::from(&destination);
-                path.push(format!("figure_{}.png", i));
+        for (i, figure) in figures.iter_mut().enumerate() {
+            let mut path = PathBuf::from(&destination);
+            path.push(format!("figure_{}.png", i));
 
-                debug!("save file : {}", path.to_str().unwrap());
+            debug!("save file : {}", path.to_str().unwrap());
 
-                figure.save_to_png(
-                    path.to_str().unwrap(),
-                    1000, 666
-                ).unwrap();
-            }
-        },
-        None => {}
+            figure.save_to_png(
+                path.to_str().unwrap(),
+                1000, 666
+            ).unwrap();
+        }
     }
 
-    if opt.no_show == false {
+    if !opt.no_show {
         for figure in figures.iter_mut() {
             figure.show().expect("Could not show figure");
         }
@@ -225,6 +587,90 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+/// Read a graph file, exiting with a readable message if it cannot be parsed. Helper function, not
+/// meant to be reused in an other context
+fn load_graph(input: &GraphInput, lenient: bool) -> Graph {
+    let filename = input.file.to_str().unwrap();
+    let format = match input.format {
+        GraphFileFormat::Tij { .. } => GraphFileFormat::Tij {
+            period: input.period,
+            gap: input.gap,
+        },
+        format => format,
+    };
+
+    let options = ReadOptions {
+        lenient,
+        time_step: input.time_step,
+    };
+
+    let (graph, skipped) = Graph::from_file_with_options(filename, format, options)
+        .unwrap_or_else(|e| {
+            error!("could not read {}: {}", filename, e);
+            std::process::exit(1);
+        });
+
+    if skipped > 0 {
+        warn!("skipped {} malformed lines in {}", skipped, filename);
+    }
+    debug!("time origin: {}, time step: {}", graph.epoch, graph.time_step);
+
+    graph
+}
+
+/// Convert the fitting options given in the time unit of the file to time steps of `graph`.
+/// Helper function, not meant to be reused in an other context
+fn fit_options(graph: &Graph, fit: &FitArgs, duration: Option<f64>) -> FitOptions {
+    let samples = |value: f64| ((value / graph.time_step).round() as i32).max(1);
+
+    let communities = fit.communities.as_ref().map(|communities| {
+        let filename = communities.to_str().unwrap();
+
+        Graph::read_labels(filename).unwrap_or_else(|e| {
+            error!("could not read {}: {}", filename, e);
+            std::process::exit(1);
+        })
+    });
+
+    FitOptions {
+        period: fit.model_period.map(samples),
+        duration: duration.map(samples),
+        duration_law: fit.duration_law,
+        communities,
+        ..Default::default()
+    }
+}
+
+/// Use the seed given by the user or draw one, and log it so that the simulation can be repeated.
+/// Helper function, not meant to be reused in an other context
+fn choose_seed(seed: Option<u64>) -> u64 {
+    let seed = seed.unwrap_or_else(rand::random);
+    info!("seed: {}", seed);
+
+    seed
+}
+
+/// Parse a duration given as a number, optionally followed by a unit (`s`, `min`, `h` or `d`)
+fn parse_duration(s: &str) -> Result<f64, String> {
+    let split = s.find(|c: char| c.is_alphabetic()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+
+    let value: f64 = value.trim().parse().map_err(|_| format!("invalid duration: {}", s))?;
+    let scale = match unit {
+        "" | "s" => 1.0,
+        "m" | "min" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => return Err(format!("unknown duration unit: {}", unit)),
+    };
+
+    if value <= 0.0 {
+        return Err(String::from("duration must be positive"));
+    }
+
+    Ok(value * scale)
+}
+
 /// Analyse a graph and plot its analysed properties. Helper function, not meant to be reused in an
 /// other context
 fn analyse_graph(g: &Graph, title_prefix: &str, truncate: f32) -> Vec<Figure>{
@@ -236,9 +682,7 @@ fn analyse_graph(g: &Graph, title_prefix: &str, truncate: f32) -> Vec<Figure>{
     let mut contacts_histogram: Vec<i32> = g.inter_contact_histo();
     let max: f32 = *contacts_histogram.iter().max().unwrap_or(&0) as f32;
 
-    contacts_histogram = contacts_histogram.into_iter()
-        .filter(|&x| x >= (truncate * max) as i32)
-        .collect();
+    contacts_histogram.retain(|&x| x >= (truncate * max) as i32);
 
     // Diplay contacts histogram
     let mut histo_fig = Figure::new();
@@ -286,12 +730,16 @@ fn analyse_graph(g: &Graph, title_prefix: &str, truncate: f32) -> Vec<Figure>{
         .set_y_label("average degree", &[]);
 
     // Compute Evolving-EdgeMarkovian model parameters
-    let creation_probability = frac_created.iter().filter(|&x| x >= &0.0)
-        .sum::<f32>() / frac_created.len() as f32;
-    let deletion_probability = frac_deleted.iter().filter(|&x| x >= &0.0)
-        .sum::<f32>() / frac_deleted.len() as f32;
-    info!("average creation probability {}", creation_probability);
-    info!("average deletion probability {}", deletion_probability);
+    let estimate = models::EdgeMarkovian::estimate(g);
+    info!(
+        "creation probability {} (95% confidence interval: [{}, {}])",
+        estimate.model.creation_probability, estimate.creation_interval.0, estimate.creation_interval.1
+    );
+    info!(
+        "deletion probability {} (95% confidence interval: [{}, {}])",
+        estimate.model.deletion_probability, estimate.deletion_interval.0, estimate.deletion_interval.1
+    );
+    info!("Edge-Markovian log-likelihood {}", estimate.log_likelihood);
 
     vec![histo_fig, frac_fig, degree_fig]
 }
\ No newline at end of file
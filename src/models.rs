@@ -1,283 +1,2095 @@
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
-use std::ops::Range;
+use std::str::FromStr;
 
 use rand::{
     prelude::*,
     Rng,
     distributions::weighted::alias_method::WeightedIndex,
 };
-use indicatif::{ProgressBar, ProgressStyle, ProgressIterator};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use indicatif::{ProgressBar, ProgressStyle};
 
-use crate::graph::{Contact, Graph};
+use crate::graph::{Contact, Graph, Transitions};
 
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "json")]
+use std::{fs, io};
+
+
+/// A temporal graph model, whose parameters can be fitted on a `Graph` to generate similar graphs
+pub trait TemporalModel {
+    /// Estimate the parameters of the model from `graph`
+    fn fit(graph: &Graph) -> Self where Self: Sized;
+
+    /// Create a `Graph` from the model, drawing random numbers from `rng`
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph;
+
+    /// Name of the model, as used on the command line
+    fn name(&self) -> &'static str;
+
+    /// Log-likelihood of `graph` under the model, if it can be computed
+    fn log_likelihood(&self, graph: &Graph) -> Option<f64>;
+
+    /// Number of parameters estimated when fitting the model
+    fn n_parameters(&self) -> usize;
+
+    /// Log-likelihood and information criteria of the model on `graph`, if the likelihood can be
+    /// computed
+    ///
+    /// The observations used by the BIC are the transitions of every pair at every time step.
+    fn score(&self, graph: &Graph) -> Option<Score> {
+        let log_likelihood = self.log_likelihood(graph)?;
+
+        let n_observations: u64 = graph.transitions().iter()
+            .map(|transitions| transitions.links + transitions.non_links)
+            .sum();
+        let k = self.n_parameters() as f64;
+
+        Some(Score {
+            log_likelihood,
+            aic: 2.0 * k - 2.0 * log_likelihood,
+            bic: k * (n_observations.max(1) as f64).ln() - 2.0 * log_likelihood,
+        })
+    }
+}
+
+/// Goodness of fit of a model on a graph. The lower the information criteria, the better
+#[derive(Debug, Clone, Copy)]
+pub struct Score {
+    pub log_likelihood: f64,
+    /// Akaike information criterion
+    pub aic: f64,
+    /// Bayesian information criterion
+    pub bic: f64,
+}
+
+/// State of the pairs of nodes at `t = 0` in the graphs generated by the Edge-Markovian models
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum InitialState {
+    /// No pair is linked: the density of links then needs some time to reach its stationary value
+    #[default]
+    Empty,
+    /// Each pair is linked with the stationary probability `p / (p + d)`, `p` and `d` being its
+    /// creation and deletion probabilities
+    Stationary,
+    /// The pairs linked at `t = 0`, sorted, the nodes being numbered from 1 as in the generated
+    /// graphs
+    Snapshot(Vec<(i32, i32)>),
+}
+
+/// The kinds of `InitialState` that can be chosen when fitting a model
+#[derive(Debug, Clone, Copy)]
+pub enum InitialStateKind {
+    Empty,
+    Stationary,
+    FirstSnapshot,
+}
+
+impl FromStr for InitialStateKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "empty" => Ok(InitialStateKind::Empty),
+            "stationary" => Ok(InitialStateKind::Stationary),
+            "first-snapshot" => Ok(InitialStateKind::FirstSnapshot),
+            _ => Err(format!("unknown initial state: {}", s)),
+        }
+    }
+}
+
+impl InitialState {
+    /// Initial state of the kind `kind`, copying the first snapshot of `graph` if needed
+    pub fn new(kind: InitialStateKind, graph: &Graph) -> InitialState {
+        match kind {
+            InitialStateKind::Empty => InitialState::Empty,
+            InitialStateKind::Stationary => InitialState::Stationary,
+            InitialStateKind::FirstSnapshot => InitialState::first_snapshot(graph),
+        }
+    }
+
+    /// The pairs linked in the snapshot `t = 0` of `graph`, the nodes being numbered by their rank
+    /// in `graph.nodes`, from 1
+    pub fn first_snapshot(graph: &Graph) -> InitialState {
+        let ranks: HashMap<i32, i32> = graph.nodes.iter()
+            .enumerate()
+            .map(|(position, &node)| (node, position as i32 + 1))
+            .collect();
+
+        let mut pairs: Vec<(i32, i32)> = graph.contacts.iter()
+            .filter(|contact| contact.start <= 0 && contact.end >= 0)
+            .filter_map(|contact| {
+                let a = *ranks.get(&contact.couple.0)?;
+                let b = *ranks.get(&contact.couple.1)?;
+
+                Some((a.min(b), a.max(b)))
+            })
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+
+        InitialState::Snapshot(pairs)
+    }
+
+    /// Whether `couple`, created with probability `creation_probability` and deleted with
+    /// probability `deletion_probability` at each time step, is linked at `t = 0`
+    ///
+    /// A random number is only drawn for the stationary state, so that the graphs generated from
+    /// an empty state do not change.
+    fn is_linked<R: Rng + ?Sized>(
+        &self,
+        couple: (i32, i32),
+        creation_probability: f32,
+        deletion_probability: f32,
+        rng: &mut R,
+    ) -> bool {
+        match self {
+            InitialState::Empty => false,
+            InitialState::Stationary => {
+                let total = creation_probability + deletion_probability;
+
+                total > 0.0 && rng.gen::<f32>() < creation_probability / total
+            }
+            InitialState::Snapshot(pairs) => pairs.binary_search(&couple).is_ok(),
+        }
+    }
+}
 
 /// Edge-Markovian graph model properties
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 pub struct EdgeMarkovian {
     pub creation_probability: f32,
     pub deletion_probability: f32,
     pub duration: i32,
     pub number_of_nodes: i32,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub initial_state: InitialState,
 }
 
-/// Create a `Graph` from an Edge-Markovian model.
-/// We consider here that at `t = 0`, there are no links
-///
-/// The complexity is `O(n^2 * T)` with `n` the number of nodes and `T` the total duration of the
-/// experiment.
-impl From<EdgeMarkovian> for Graph {
-    fn from(model: EdgeMarkovian) -> Graph {
-        // Represents a pair : (n1, n2, is connected, id of the contact assigned)
-        let mut pairs: Vec<(i32, i32, bool, usize)> = Vec::with_capacity(
-            ((model.number_of_nodes * (model.number_of_nodes - 1)) / 2) as usize,
-        ); // nCr(n, 2) = n(n-1)/2
-
-        for i_node1 in 1..=model.number_of_nodes {
-            for i_node2 in (i_node1 + 1)..=model.number_of_nodes {
-                pairs.push((i_node1, i_node2, false, core::usize::MAX));
-            }
-        }
+impl TemporalModel for EdgeMarkovian {
+    /// Maximum likelihood estimation of the probabilities, see `EdgeMarkovian::estimate`
+    fn fit(graph: &Graph) -> EdgeMarkovian {
+        EdgeMarkovian::estimate(graph).model
+    }
 
-        let mut rng = rand::thread_rng();
-        let mut rand_num: f32;
+    /// Create a `Graph` from an Edge-Markovian model, drawing random numbers from `rng`.
+    /// The pairs linked at `t = 0` are given by `initial_state`
+    ///
+    /// Instead of drawing the state of every pair at every time step, the time before the next
+    /// creation or deletion of each pair is drawn from a geometric law: the complexity is
+    /// `O(n^2 + c)` with `n` the number of nodes and `c` the number of contacts generated.
+    ///
+    /// The pairs are generated in parallel by `generate_pairs`.
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        let contacts = generate_pairs(self.number_of_nodes, rng, |pair, rng, contacts| {
+            let linked = self.initial_state.is_linked(
+                pair,
+                self.creation_probability,
+                self.deletion_probability,
+                rng,
+            );
 
-        let mut contacts: Vec<Contact> = Vec::with_capacity(
-            (model.creation_probability * model.duration as f32) as usize * pairs.len(),
-        );
+            generate_edge_markovian_pair(
+                pair,
+                self.creation_probability,
+                self.deletion_probability,
+                linked,
+                self.duration,
+                rng,
+                contacts,
+            );
+        });
 
-        // Progress bar
-        let pb = ProgressBar::new(model.duration as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.yellow/blue}] {percent}% ({eta})")
-            .progress_chars("#>-"));
+        Graph {
+            duration: self.duration,
+            nodes: (1..=self.number_of_nodes).collect(),
+            contacts,
+            ..Default::default()
+        }
+    }
 
-        for t in (1..=model.duration).progress_with(pb) {
-            for pair in pairs.iter_mut() {
-                // Generate number in (0, 1[
-                rand_num = rng.gen();
+    fn name(&self) -> &'static str {
+        "edge-markovian"
+    }
 
-                // If (n_1, n_2) is in E_{t-1}, delete pair with probability d
-                if pair.2 == true && rand_num <= model.deletion_probability {
-                    contacts[pair.3].end = t;
+    fn log_likelihood(&self, graph: &Graph) -> Option<f64> {
+        Some(transitions_log_likelihood(
+            graph,
+            |_| self.creation_probability,
+            |_| self.deletion_probability,
+        ))
+    }
 
-                    pair.2 = false;
-                    pair.3 = core::usize::MAX;
-                }
+    fn n_parameters(&self) -> usize {
+        2
+    }
+}
 
-                // Generate number in (0, 1[
-                rand_num = rng.gen();
+/// Create a `Graph` from the model, seeding the random generator from the system
+impl From<EdgeMarkovian> for Graph {
+    fn from(model: EdgeMarkovian) -> Graph {
+        model.generate(&mut rand::thread_rng())
+    }
+}
 
-                // If (n_1, n_2) is not in E_{t-1}, create pair with probability p
-                if pair.2 == false && rand_num <= model.creation_probability {
-                    contacts.push(Contact {
-                        start: t,
-                        couple: (pair.0, pair.1),
-                        end: 0,
-                    });
+/// Maximum likelihood estimation of an `EdgeMarkovian` model
+pub struct EdgeMarkovianEstimate {
+    pub model: EdgeMarkovian,
+    /// 95% confidence interval of the creation probability
+    pub creation_interval: (f32, f32),
+    /// 95% confidence interval of the deletion probability
+    pub deletion_interval: (f32, f32),
+    /// Log-likelihood of the graph under the estimated model
+    pub log_likelihood: f64,
+}
 
-                    pair.2 = true;
-                    pair.3 = contacts.len() - 1;
-                }
-            }
+impl EdgeMarkovian {
+    /// Estimate the probabilities of the model maximizing the likelihood of `graph`
+    ///
+    /// The creation probability is the number of pairs created from a snapshot to the next one
+    /// divided by the number of pairs that could have been created, over the whole graph. The
+    /// deletion probability is obtained the same way from the deleted pairs.
+    pub fn estimate(graph: &Graph) -> EdgeMarkovianEstimate {
+        let mut total = Transitions::default();
+        for transitions in graph.transitions() {
+            total.links += transitions.links;
+            total.non_links += transitions.non_links;
+            total.created += transitions.created;
+            total.deleted += transitions.deleted;
         }
 
-        // Remove the contacts that could not end
-        contacts = contacts.into_iter().filter(|c| c.end != 0).collect();
-
-        let graph = Graph {
-            duration: model.duration,
-            nodes: Range {
-                start: 1,
-                end: model.number_of_nodes,
-            }
-            .collect(),
-            contacts,
+        let model = EdgeMarkovian {
+            duration: graph.duration,
+            number_of_nodes: graph.nodes.len() as i32,
+            creation_probability: ratio(total.created, total.non_links),
+            deletion_probability: ratio(total.deleted, total.links),
+            initial_state: InitialState::Empty,
         };
+        let log_likelihood = transitions_log_likelihood(
+            graph,
+            |_| model.creation_probability,
+            |_| model.deletion_probability,
+        );
 
-        graph
+        EdgeMarkovianEstimate {
+            model,
+            creation_interval: wilson_interval(total.created, total.non_links),
+            deletion_interval: wilson_interval(total.deleted, total.links),
+            log_likelihood,
+        }
     }
 }
 
 /// Edge-Markovian model properties with time dependent creation/deletion probabilities
 /// `creation_probability` and `deletion_probability` must have `n = duration` values
+///
+/// The stationary initial state uses the average creation and deletion probabilities.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 pub struct TimeDependentEdgeMarkovian {
     pub creation_probability: Vec<f32>,
     pub deletion_probability: Vec<f32>,
     pub duration: i32,
     pub number_of_nodes: i32,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub initial_state: InitialState,
+}
+
+impl TemporalModel for TimeDependentEdgeMarkovian {
+    /// Maximum likelihood estimation of the probabilities at each time step, see
+    /// `step_probabilities`
+    fn fit(graph: &Graph) -> TimeDependentEdgeMarkovian {
+        let (creation_probability, deletion_probability) = step_probabilities(graph);
+
+        TimeDependentEdgeMarkovian {
+            duration: graph.duration,
+            number_of_nodes: graph.nodes.len() as i32,
+            creation_probability,
+            deletion_probability,
+            initial_state: InitialState::Empty,
+        }
+    }
+
+    /// Create a `Graph` from a Edge-Markovian model with time dependent creation and deletion
+    /// probabilities, drawing random numbers from `rng`. The pairs linked at `t = 0` are given by
+    /// `initial_state`
+    ///
+    /// The pairs are generated in parallel by `generate_pairs`.
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        // The probabilities of the step 0 are not used
+        let creation_probability = mean(self.creation_probability.get(1..).unwrap_or(&[]));
+        let deletion_probability = mean(self.deletion_probability.get(1..).unwrap_or(&[]));
+
+        let contacts = generate_pairs(self.number_of_nodes, rng, |pair, rng, contacts| {
+            let linked = self.initial_state
+                .is_linked(pair, creation_probability, deletion_probability, rng);
+
+            generate_step_by_step_pair(
+                pair,
+                |t| (self.creation_probability[t as usize], self.deletion_probability[t as usize]),
+                linked,
+                self.duration,
+                rng,
+                contacts,
+            );
+        });
+
+        Graph {
+            duration: self.duration,
+            nodes: (1..=self.number_of_nodes).collect(),
+            contacts,
+            ..Default::default()
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "time-dependent-edge-markovian"
+    }
+
+    fn log_likelihood(&self, graph: &Graph) -> Option<f64> {
+        Some(transitions_log_likelihood(
+            graph,
+            |t| self.creation_probability.get(t).copied().unwrap_or(0.0),
+            |t| self.deletion_probability.get(t).copied().unwrap_or(0.0),
+        ))
+    }
+
+    fn n_parameters(&self) -> usize {
+        2 * self.duration as usize
+    }
 }
 
-/// Create a `Graph` from a Edge-Markovian model with time dependent creation and deletion
-/// probabilities. We consider here that at `t = 0`, there are no links
+/// Create a `Graph` from the model, seeding the random generator from the system
 impl From<TimeDependentEdgeMarkovian> for Graph {
     fn from(model: TimeDependentEdgeMarkovian) -> Graph {
-        // Represents a pair : (n1, n2, is connected, id of the contact assigned)
-        let mut pairs: Vec<(i32, i32, bool, usize)> = Vec::with_capacity(
-            ((model.number_of_nodes * (model.number_of_nodes - 1)) / 2) as usize,
-        ); // nCr(n, 2) = n(n-1)/2
-
-        for i_node1 in 1..=model.number_of_nodes {
-            for i_node2 in (i_node1 + 1)..=model.number_of_nodes {
-                pairs.push((i_node1, i_node2, false, core::usize::MAX));
-            }
+        model.generate(&mut rand::thread_rng())
+    }
+}
+
+/// Law of a duration, in time steps
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum DurationLaw {
+    /// Law observed in a graph, `probabilities[k]` being the probability of a duration of `k` time
+    /// steps
+    Empirical(Vec<f64>),
+    /// Geometric law: the duration is the number of trials needed to get a success of this
+    /// probability, as in the Edge-Markovian models
+    Geometric(f32),
+    /// Power law of durations of 1 to `max` time steps, the probability of `k` time steps being
+    /// proportional to `k^-exponent`
+    PowerLaw { exponent: f32, max: i32 },
+}
+
+/// The families of `DurationLaw` that can be fitted
+#[derive(Debug, Clone, Copy)]
+pub enum DurationLawKind {
+    Empirical,
+    Geometric,
+    PowerLaw,
+}
+
+impl FromStr for DurationLawKind {
+    type Err = String;
+
+    /// Parse a law name as used on the command line (`empirical`, `geometric` or `power-law`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "empirical" => Ok(DurationLawKind::Empirical),
+            "geometric" => Ok(DurationLawKind::Geometric),
+            "power-law" => Ok(DurationLawKind::PowerLaw),
+            _ => Err(format!("unknown duration law: {}", s)),
         }
+    }
+}
 
-        let mut rng = rand::thread_rng();
-        let mut rand_num: f32;
+impl DurationLaw {
+    /// Fit a law of the family `kind` on `durations`, of 1 to `max` time steps
+    ///
+    /// `censored` are durations still running at the end of the graph, only known to be longer
+    /// than their value. The empirical law is the Kaplan-Meier estimate, where a censored duration
+    /// only counts as lasting longer than its value: the probability of lasting longer than every
+    /// observed duration, if any, is put on `max + 1`. The geometric law takes them into account
+    /// in its maximum likelihood estimation and the power law ignores them.
+    ///
+    /// The geometric and power laws are fitted by maximum likelihood, the exponent of the power law
+    /// using the usual continuous approximation. Without any duration, the law gives durations
+    /// that never end.
+    pub fn fit(kind: DurationLawKind, durations: &[i32], censored: &[i32], max: i32) -> DurationLaw {
+        if durations.is_empty() {
+            return DurationLaw::Geometric(0.0);
+        }
+        let n = durations.len() as f64;
 
-        let mut contacts: Vec<Contact> = Vec::with_capacity(
-            (model.duration / 100) as usize * pairs.len(),
-        );
+        match kind {
+            DurationLawKind::Empirical => {
+                let longest = *durations.iter().max().unwrap();
 
-        // Progress bar
-        let pb = ProgressBar::new(model.duration as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.yellow/blue}] {percent}% ({eta})")
-            .progress_chars("#>-"));
+                let mut events = vec![0u64; longest as usize + 1];
+                for &duration in durations {
+                    events[duration as usize] += 1;
+                }
+                // A censored duration `c` is known to last at least `k` time steps for `k <= c`,
+                // it leaves the durations at risk at `c + 1`
+                let mut censored_left = vec![0u64; longest as usize + 2];
+                for &duration in censored {
+                    censored_left[(duration.clamp(-1, longest) + 1) as usize] += 1;
+                }
 
-        for t in (1..=model.duration).progress_with(pb) {
-            for pair in pairs.iter_mut() {
-                // Generate number in (0, 1[
-                rand_num = rng.gen();
+                // Durations known to last at least `k` time steps, updated from k = 0
+                let mut at_risk = (durations.len() + censored.len()) as u64;
+                // Probability of lasting at least `k` time steps
+                let mut survival = 1.0;
+                let mut probabilities = vec![0.0; longest as usize + 1];
 
-                // If (n_1, n_2) is in E_{t-1}, delete pair with probability d
-                if pair.2 == true && rand_num <= model.deletion_probability[t as usize] {
-                    contacts[pair.3].end = t;
+                for k in 0..=longest as usize {
+                    at_risk -= censored_left[k];
+                    if at_risk == 0 {
+                        break;
+                    }
 
-                    pair.2 = false;
-                    pair.3 = core::usize::MAX;
+                    probabilities[k] = survival * events[k] as f64 / at_risk as f64;
+                    survival -= probabilities[k];
+                    at_risk -= events[k];
                 }
 
-                // Generate number in (0, 1[
-                rand_num = rng.gen();
+                if survival > 1e-12 {
+                    probabilities.resize(max.max(longest) as usize + 1, 0.0);
+                    probabilities.push(survival);
+                }
 
-                // If (n_1, n_2) is not in E_{t-1}, create pair with probability p
-                if pair.2 == false && rand_num <= model.creation_probability[t as usize] {
-                    contacts.push(Contact {
-                        start: t,
-                        couple: (pair.0, pair.1),
-                        end: 0,
-                    });
+                DurationLaw::Empirical(probabilities)
+            }
+            DurationLawKind::Geometric => {
+                let total = durations.iter().chain(censored).map(|&d| d as f64).sum::<f64>();
 
-                    pair.2 = true;
-                    pair.3 = contacts.len() - 1;
+                DurationLaw::Geometric((n / total) as f32)
+            }
+            DurationLawKind::PowerLaw => {
+                let log_sum: f64 = durations.iter().map(|&d| (d as f64 / 0.5).ln()).sum();
+
+                DurationLaw::PowerLaw {
+                    exponent: (1.0 + n / log_sum).max(1.001) as f32,
+                    max: max.max(*durations.iter().max().unwrap()),
                 }
             }
         }
+    }
 
-        // Remove the contacts that could not end
-        contacts = contacts.into_iter().filter(|c| c.end != 0).collect();
+    /// Logarithm of the probability of a duration of `k` time steps
+    pub fn log_probability(&self, k: i32) -> f64 {
+        match self {
+            DurationLaw::Empirical(probabilities) => {
+                probabilities.get(k as usize).copied().unwrap_or(0.0).ln()
+            }
+            DurationLaw::Geometric(p) => {
+                let p = *p as f64;
 
-        let graph = Graph {
-            duration: model.duration,
-            nodes: Range {
-                start: 1,
-                end: model.number_of_nodes,
+                (k - 1) as f64 * (1.0 - p).ln() + p.ln()
             }
-                .collect(),
-            contacts,
-        };
+            DurationLaw::PowerLaw { exponent, max } => {
+                if k < 1 || k > *max {
+                    return f64::NEG_INFINITY;
+                }
 
-        graph
+                -(*exponent as f64) * (k as f64).ln() - self.power_law_normalisation().ln()
+            }
+        }
+    }
+
+    /// Logarithm of the probability of a duration longer than `k` time steps
+    pub fn log_survival(&self, k: i32) -> f64 {
+        match self {
+            DurationLaw::Empirical(probabilities) => {
+                probabilities.iter().skip((k + 1).max(0) as usize).sum::<f64>().ln()
+            }
+            DurationLaw::Geometric(p) => k.max(0) as f64 * (1.0 - *p as f64).ln(),
+            DurationLaw::PowerLaw { exponent, max } => {
+                let longer: f64 = ((k.max(0) + 1)..=*max)
+                    .map(|j| (j as f64).powf(-(*exponent as f64)))
+                    .sum();
+
+                (longer / self.power_law_normalisation()).ln()
+            }
+        }
+    }
+
+    /// Number of parameters of the law
+    pub fn n_parameters(&self) -> usize {
+        match self {
+            DurationLaw::Empirical(probabilities) => {
+                probabilities.iter().filter(|&&probability| probability > 0.0).count()
+            }
+            DurationLaw::Geometric(_) | DurationLaw::PowerLaw { .. } => 1,
+        }
+    }
+
+    /// Sum of `k^-exponent` over the durations of a power law
+    fn power_law_normalisation(&self) -> f64 {
+        match self {
+            DurationLaw::PowerLaw { exponent, max } => {
+                (1..=*max).map(|k| (k as f64).powf(-(*exponent as f64))).sum()
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Prepare the law to draw durations from it
+    fn sampler(&self) -> DurationSampler {
+        match self {
+            DurationLaw::Empirical(probabilities) => DurationSampler::Weighted(
+                WeightedIndex::new(probabilities.clone()).unwrap()
+            ),
+            DurationLaw::Geometric(p) => DurationSampler::Geometric(*p),
+            DurationLaw::PowerLaw { exponent, max } => {
+                let weights = (0..=*max)
+                    .map(|k| if k == 0 { 0.0 } else { (k as f64).powf(-(*exponent as f64)) })
+                    .collect();
+
+                DurationSampler::Weighted(WeightedIndex::new(weights).unwrap())
+            }
+        }
     }
 }
 
-/// Edge-Markovian model properties with time dependent creation/deletion probabilities and delayed
-/// nodes
-pub struct DelayedTimeDependentEdgeMarkovian {
-    pub creation_probability: Vec<f32>,
-    pub deletion_probability: Vec<f32>,
-    pub intercontacts_histogram: Vec<i32>,
+/// `DurationLaw` ready to draw durations
+enum DurationSampler {
+    /// Durations drawn from their weights, the index being the duration
+    Weighted(WeightedIndex<f64>),
+    Geometric(f32),
+}
+
+impl DurationSampler {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> i32 {
+        match self {
+            DurationSampler::Weighted(weights) => weights.sample(rng) as i32,
+            DurationSampler::Geometric(p) => geometric(rng, *p),
+        }
+    }
+}
+
+/// Semi-Markov model properties, where each pair alternates contacts and inter-contacts whose
+/// durations follow arbitrary laws
+///
+/// A pair is linked at least once with probability `linked_fraction`, its first contact starting
+/// at a time step drawn from `first_contact`. The pairs are independent.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct SemiMarkovEdgeModel {
+    pub linked_fraction: f32,
+    pub first_contact: DurationLaw,
+    pub contact_duration: DurationLaw,
+    pub inter_contact_duration: DurationLaw,
     pub duration: i32,
     pub number_of_nodes: i32,
 }
 
-/// Create a `Graph` from a Edge-Markovian model with time dependent creation and deletion
-/// probabilities and delayed nodes. We consider here that at `t = 0`, there are no links
-impl From<DelayedTimeDependentEdgeMarkovian> for Graph {
-    fn from(model: DelayedTimeDependentEdgeMarkovian) -> Graph {
-        // Represents a pair : (n1, n2, is connected, id of the contact assigned, the time before
-        // any new connexion is prohibited)
-        let mut pairs: Vec<(i32, i32, bool, usize, i32)> = Vec::with_capacity(
-            ((model.number_of_nodes * (model.number_of_nodes - 1)) / 2) as usize,
-        ); // nCr(n, 2) = n(n-1)/2
+impl SemiMarkovEdgeModel {
+    /// Fit the model on `graph`, the contact and inter-contact durations following a law of the
+    /// family `kind`. The time of the first contacts always follows its empirical law
+    ///
+    /// A contact lasts from its start to its end included, an inter-contact is the number of time
+    /// steps between two contacts of a pair. The contacts and inter-contacts still running at the
+    /// end of the graph are censored (see `DurationLaw::fit`).
+    pub fn fit_with_law(graph: &Graph, kind: DurationLawKind) -> SemiMarkovEdgeModel {
+        let n = graph.nodes.len();
+        let n_pairs = n * n.saturating_sub(1) / 2;
+
+        let mut first_contacts = Vec::new();
+        let mut contact_durations = Vec::new();
+        let mut inter_contact_durations = Vec::new();
+        // Contacts still open at the end of the graph
+        let mut last_contacts = Vec::new();
+        // Inter-contacts after the last contact of each pair
+        let mut last_inter_contacts = Vec::new();
 
-        for i_node1 in 1..=model.number_of_nodes {
-            for i_node2 in (i_node1 + 1)..=model.number_of_nodes {
-                pairs.push((i_node1, i_node2, false, core::usize::MAX, core::i32::MAX));
+        for intervals in pair_intervals(graph) {
+            first_contacts.push(intervals[0].start.max(0));
+
+            for (i, interval) in intervals.iter().enumerate() {
+                let length = interval.end - interval.start + 1;
+
+                match intervals.get(i + 1) {
+                    Some(next) => {
+                        contact_durations.push(length);
+                        inter_contact_durations.push(next.start - interval.end - 1);
+                    }
+                    None if interval.end < graph.duration => {
+                        contact_durations.push(length);
+                        last_inter_contacts.push(graph.duration - interval.end - 1);
+                    }
+                    // Linked for `graph.duration - interval.start + 1` time steps, so lasting
+                    // longer than `graph.duration - interval.start`
+                    None => last_contacts.push(graph.duration - interval.start),
+                }
             }
         }
 
-        let mut rng = rand::thread_rng();
-        let mut rand_num: f32;
+        SemiMarkovEdgeModel {
+            linked_fraction: if n_pairs == 0 {
+                0.0
+            } else {
+                first_contacts.len() as f32 / n_pairs as f32
+            },
+            first_contact: DurationLaw::fit(
+                DurationLawKind::Empirical,
+                &first_contacts,
+                &[],
+                graph.duration,
+            ),
+            contact_duration: DurationLaw::fit(
+                kind,
+                &contact_durations,
+                &last_contacts,
+                graph.duration,
+            ),
+            inter_contact_duration: DurationLaw::fit(
+                kind,
+                &inter_contact_durations,
+                &last_inter_contacts,
+                graph.duration,
+            ),
+            duration: graph.duration,
+            number_of_nodes: n as i32,
+        }
+    }
+}
 
-        let mut contacts: Vec<Contact> = Vec::with_capacity(
-            (model.duration / 100) as usize * pairs.len(),
-        );
+impl TemporalModel for SemiMarkovEdgeModel {
+    /// Use the empirical laws of the durations, see `SemiMarkovEdgeModel::fit_with_law`
+    fn fit(graph: &Graph) -> SemiMarkovEdgeModel {
+        SemiMarkovEdgeModel::fit_with_law(graph, DurationLawKind::Empirical)
+    }
 
-        // Create the inter-contacts distribution from the histogram
-        let values: Vec<i32> = (-1..model.intercontacts_histogram.len() as i32 -1).collect();
-        let intercontacts_dist = WeightedIndex::new(
-            model.intercontacts_histogram
-        ).unwrap();
+    /// Create a `Graph` from a semi-Markov model, drawing random numbers from `rng`
+    ///
+    /// The pairs are generated in parallel by `generate_pairs`.
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        let first_contact = self.first_contact.sampler();
+        let contact_duration = self.contact_duration.sampler();
+        let inter_contact_duration = self.inter_contact_duration.sampler();
 
-        // Progress bar
-        let pb = ProgressBar::new(model.duration as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.yellow/blue}] {percent}% ({eta})")
-            .progress_chars("#>-"));
+        let contacts = generate_pairs(self.number_of_nodes, rng, |pair, rng, contacts| {
+            if rng.gen::<f32>() >= self.linked_fraction {
+                return;
+            }
 
-        for t in (1..=model.duration).progress_with(pb) {
-            for pair in pairs.iter_mut() {
-                // Generate number in (0, 1[
-                rand_num = rng.gen();
+            let mut start = first_contact.sample(rng);
+            while start <= self.duration {
+                let end = start.saturating_add(contact_duration.sample(rng) - 1);
 
-                // If (n_1, n_2) is in E_{t-1}, delete pair with probability d
-                if pair.2 == true && rand_num <= model.deletion_probability[t as usize] {
-                    contacts[pair.3].end = t;
+                // The contact still open at the end is cut at `duration`
+                contacts.push(Contact {
+                    start,
+                    couple: pair,
+                    end: end.min(self.duration),
+                });
 
-                    pair.2 = false;
-                    pair.3 = core::usize::MAX;
+                start = end.saturating_add(1).saturating_add(inter_contact_duration.sample(rng));
+            }
+        });
 
-                    let delay = values[intercontacts_dist.sample(&mut rng)];
-                    pair.4 = t + delay;
-                }
+        Graph {
+            duration: self.duration,
+            nodes: (1..=self.number_of_nodes).collect(),
+            contacts,
+            ..Default::default()
+        }
+    }
 
-                // If (n_1, n_2) is not in E_{t-1}, create pair with probability p
-                // and pair is not delayed
-                // Generate number in (0, 1[
-                rand_num = rng.gen();
+    fn name(&self) -> &'static str {
+        "semi-markov"
+    }
 
-                if pair.2 == false
-                    && pair.4 >= t
-                    && rand_num <= model.creation_probability[t as usize] {
-                    contacts.push(Contact {
-                        start: t,
-                        couple: (pair.0, pair.1),
-                        end: 0,
-                    });
+    /// The contact or inter-contact still running at the end of `graph` counts with the
+    /// probability of lasting at least until the end
+    fn log_likelihood(&self, graph: &Graph) -> Option<f64> {
+        let n = graph.nodes.len() as u64;
+        let n_pairs = n * n.saturating_sub(1) / 2;
+
+        let pairs = pair_intervals(graph);
+        let mut log_likelihood = bernoulli_log_likelihood(
+            pairs.len() as u64,
+            n_pairs,
+            self.linked_fraction as f64,
+        );
+
+        for intervals in pairs {
+            log_likelihood += self.first_contact.log_probability(intervals[0].start.max(0));
 
-                    pair.2 = true;
-                    pair.3 = contacts.len() - 1;
-                    pair.4 = core::i32::MAX;
+            for (i, interval) in intervals.iter().enumerate() {
+                let length = interval.end - interval.start + 1;
 
-                    break;
+                match intervals.get(i + 1) {
+                    Some(next) => {
+                        log_likelihood += self.contact_duration.log_probability(length);
+                        log_likelihood += self.inter_contact_duration
+                            .log_probability(next.start - interval.end - 1);
+                    }
+                    None if interval.end < graph.duration => {
+                        log_likelihood += self.contact_duration.log_probability(length);
+                        log_likelihood += self.inter_contact_duration
+                            .log_survival(graph.duration - interval.end - 1);
+                    }
+                    None => {
+                        log_likelihood += self.contact_duration
+                            .log_survival(graph.duration - interval.start);
+                    }
                 }
             }
         }
 
-        // Remove the contacts that could not end
-        contacts = contacts.into_iter().filter(|c| c.end != 0).collect();
+        Some(log_likelihood)
+    }
+
+    fn n_parameters(&self) -> usize {
+        1 + self.first_contact.n_parameters()
+            + self.contact_duration.n_parameters()
+            + self.inter_contact_duration.n_parameters()
+    }
+}
+
+/// Create a `Graph` from the model, seeding the random generator from the system
+impl From<SemiMarkovEdgeModel> for Graph {
+    fn from(model: SemiMarkovEdgeModel) -> Graph {
+        model.generate(&mut rand::thread_rng())
+    }
+}
+
+/// Creation and deletion probabilities of a pair of nodes
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct PairProbabilities {
+    pub couple: (i32, i32),
+    pub creation_probability: f32,
+    pub deletion_probability: f32,
+}
+
+/// Edge-Markovian model properties where each pair of nodes has its own creation/deletion
+/// probabilities. The pairs that are not listed in `pairs` are never linked
+///
+/// The nodes of the pairs of `initial_state` are numbered by their rank in `nodes`, from 1.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct HeterogeneousEdgeMarkovian {
+    pub pairs: Vec<PairProbabilities>,
+    pub nodes: Vec<i32>,
+    pub duration: i32,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub initial_state: InitialState,
+}
+
+impl TemporalModel for HeterogeneousEdgeMarkovian {
+    /// Maximum likelihood estimation of the probabilities of each pair, as in
+    /// `EdgeMarkovian::estimate`. The pairs never linked get a creation probability of 0
+    fn fit(graph: &Graph) -> HeterogeneousEdgeMarkovian {
+        let pairs = graph.pair_transitions().into_iter()
+            .map(|(couple, transitions)| PairProbabilities {
+                couple,
+                creation_probability: ratio(transitions.created, transitions.non_links),
+                deletion_probability: ratio(transitions.deleted, transitions.links),
+            })
+            .collect();
+
+        HeterogeneousEdgeMarkovian {
+            pairs,
+            nodes: graph.nodes.clone(),
+            duration: graph.duration,
+            initial_state: InitialState::Empty,
+        }
+    }
+
+    /// Create a `Graph` from the model as `EdgeMarkovian` does, each pair with its own
+    /// probabilities. Only the listed pairs are generated, in parallel by `generate_listed_pairs`
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        let ranks: HashMap<i32, i32> = self.nodes.iter()
+            .enumerate()
+            .map(|(position, &node)| (node, position as i32 + 1))
+            .collect();
+
+        let contacts = generate_listed_pairs(&self.pairs, rng, |pair, rng, contacts| {
+            let (a, b) = (ranks[&pair.couple.0], ranks[&pair.couple.1]);
+            let linked = self.initial_state.is_linked(
+                (a.min(b), a.max(b)),
+                pair.creation_probability,
+                pair.deletion_probability,
+                rng,
+            );
+
+            generate_edge_markovian_pair(
+                pair.couple,
+                pair.creation_probability,
+                pair.deletion_probability,
+                linked,
+                self.duration,
+                rng,
+                contacts,
+            );
+        });
+
+        Graph {
+            duration: self.duration,
+            nodes: self.nodes.clone(),
+            contacts,
+            ..Default::default()
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "heterogeneous-edge-markovian"
+    }
+
+    /// The pairs that are not listed are never created, they do not change the likelihood
+    fn log_likelihood(&self, graph: &Graph) -> Option<f64> {
+        let probabilities: HashMap<(i32, i32), (f32, f32)> = self.pairs.iter()
+            .map(|pair| (pair.couple, (pair.creation_probability, pair.deletion_probability)))
+            .collect();
+
+        let mut log_likelihood = 0.0;
+        for (couple, transitions) in graph.pair_transitions() {
+            let (creation_probability, deletion_probability) = probabilities.get(&couple)
+                .copied()
+                .unwrap_or((0.0, 0.0));
+
+            log_likelihood += bernoulli_log_likelihood(
+                transitions.created,
+                transitions.non_links,
+                creation_probability as f64,
+            ) + bernoulli_log_likelihood(
+                transitions.deleted,
+                transitions.links,
+                deletion_probability as f64,
+            );
+        }
+
+        Some(log_likelihood)
+    }
+
+    /// Two probabilities per listed pair, and a creation probability for the others
+    fn n_parameters(&self) -> usize {
+        let n = self.nodes.len();
+
+        n * n.saturating_sub(1) / 2 + self.pairs.len()
+    }
+}
+
+/// Activity-driven model properties
+///
+/// At each time step, each node is active with a probability given by its activity, and then
+/// creates `contacts_per_activation` contacts of `contact_duration` time steps with nodes chosen at
+/// random. The activity of each node is drawn from `activities`.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ActivityDriven {
+    pub activities: Vec<f32>,
+    pub contacts_per_activation: i32,
+    pub contact_duration: i32,
+    pub duration: i32,
+    pub number_of_nodes: i32,
+}
+
+impl TemporalModel for ActivityDriven {
+    /// Compute the activity of each node from its number of contacts, each active node creating a
+    /// single contact of the average contact duration of `graph`
+    ///
+    /// A node with activity `a_i` takes part in `T * (a_i + (A - a_i) / (n - 1))` contacts on
+    /// average, with `A` the sum of the activities: the activities are obtained by solving these
+    /// equations, `A` being given by the total number of contacts.
+    fn fit(graph: &Graph) -> ActivityDriven {
+        let n = graph.nodes.len();
+        let duration = graph.duration.max(1) as f64;
+
+        let mut node_contacts: HashMap<i32, u64> = HashMap::new();
+        for contact in &graph.contacts {
+            *node_contacts.entry(contact.couple.0).or_insert(0) += 1;
+            *node_contacts.entry(contact.couple.1).or_insert(0) += 1;
+        }
+
+        let total_activity = graph.contacts.len() as f64 / duration;
+        let activities = graph.nodes.iter()
+            .map(|node| {
+                let contacts = *node_contacts.get(node).unwrap_or(&0) as f64 / duration;
+                let activity = if n > 2 {
+                    (contacts - total_activity / (n - 1) as f64) / (1.0 - 1.0 / (n - 1) as f64)
+                } else {
+                    contacts
+                };
+
+                activity.clamp(0.0, 1.0) as f32
+            })
+            .collect();
+
+        let contact_duration = if graph.contacts.is_empty() {
+            1
+        } else {
+            let total: i64 = graph.contacts.iter().map(|c| (c.end - c.start + 1) as i64).sum();
+
+            ((total as f64 / graph.contacts.len() as f64).round() as i32).max(1)
+        };
+
+        ActivityDriven {
+            activities,
+            contacts_per_activation: 1,
+            contact_duration,
+            duration: graph.duration,
+            number_of_nodes: n as i32,
+        }
+    }
+
+    /// Create a `Graph` from an activity-driven model, drawing random numbers from `rng`
+    ///
+    /// The time between two activations of a node is drawn from a geometric law. Contacts of a
+    /// same pair that overlap are merged, and contacts are cut at `duration`.
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        let mut contacts: Vec<Contact> = Vec::new();
+
+        if self.number_of_nodes >= 2 && !self.activities.is_empty() {
+            let m = self.contacts_per_activation.clamp(0, self.number_of_nodes - 1) as usize;
 
-        let graph = Graph {
-            duration: model.duration,
-            nodes: Range {
-                start: 1,
-                end: model.number_of_nodes,
+            for node in 1..=self.number_of_nodes {
+                let activity = self.activities[rng.gen_range(0, self.activities.len())];
+                let mut t: i32 = 0;
+
+                loop {
+                    t = t.saturating_add(geometric(rng, activity));
+                    if t > self.duration {
+                        break;
+                    }
+
+                    // Choose m other nodes
+                    let others = rand::seq::index::sample(rng, self.number_of_nodes as usize - 1, m);
+                    for other in others.iter() {
+                        // Skip the active node
+                        let other = if other as i32 + 1 >= node {
+                            other as i32 + 2
+                        } else {
+                            other as i32 + 1
+                        };
+
+                        contacts.push(Contact {
+                            start: t,
+                            couple: (node.min(other), node.max(other)),
+                            end: (t + self.contact_duration - 1).min(self.duration),
+                        });
+                    }
+                }
             }
-                .collect(),
+        }
+
+        let mut graph = Graph {
+            duration: self.duration,
+            nodes: (1..=self.number_of_nodes).collect(),
             contacts,
+            ..Default::default()
         };
+        graph.contacts = graph.link_intervals();
+        graph.contacts.sort_by_key(|c| c.start);
 
         graph
     }
-}
\ No newline at end of file
+
+    fn name(&self) -> &'static str {
+        "activity-driven"
+    }
+
+    /// The node that created a contact is not known, the likelihood is not computed
+    fn log_likelihood(&self, _graph: &Graph) -> Option<f64> {
+        None
+    }
+
+    fn n_parameters(&self) -> usize {
+        self.activities.len() + 2
+    }
+}
+
+/// Edge-Markovian model properties with creation/deletion probabilities periodic in time, e.g.
+/// following a daily cycle. `creation_probability` and `deletion_probability` have `period`
+/// values, the probabilities at the time step `t` being the ones of `t % period`
+///
+/// The stationary initial state uses the average creation and deletion probabilities over a
+/// period.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct PeriodicEdgeMarkovian {
+    pub creation_probability: Vec<f32>,
+    pub deletion_probability: Vec<f32>,
+    pub period: i32,
+    pub duration: i32,
+    pub number_of_nodes: i32,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub initial_state: InitialState,
+}
+
+impl PeriodicEdgeMarkovian {
+    /// Maximum likelihood estimation of the probabilities for a given `period`, in time steps
+    ///
+    /// The transitions of `graph` are folded over the period: the creation probability of a phase
+    /// is the number of pairs created at the time steps of this phase divided by the number of
+    /// pairs that could have been. The deletion probability is obtained the same way.
+    pub fn fit_with_period(graph: &Graph, period: i32) -> PeriodicEdgeMarkovian {
+        let period = period.max(1);
+
+        let mut folded = vec![Transitions::default(); period as usize];
+        for (t, transitions) in graph.transitions().iter().enumerate().skip(1) {
+            let phase = &mut folded[t % period as usize];
+
+            phase.links += transitions.links;
+            phase.non_links += transitions.non_links;
+            phase.created += transitions.created;
+            phase.deleted += transitions.deleted;
+        }
+
+        let (creation_probability, deletion_probability) = folded.iter()
+            .map(|phase| (ratio(phase.created, phase.non_links), ratio(phase.deleted, phase.links)))
+            .unzip();
+
+        PeriodicEdgeMarkovian {
+            creation_probability,
+            deletion_probability,
+            period,
+            duration: graph.duration,
+            number_of_nodes: graph.nodes.len() as i32,
+            initial_state: InitialState::Empty,
+        }
+    }
+}
+
+impl TemporalModel for PeriodicEdgeMarkovian {
+    /// Choose the period among one day, one week (assuming that the time unit of `graph` is the
+    /// second) and no period at all, keeping the one with the lowest BIC. A period is only
+    /// considered if the graph lasts at least two periods
+    fn fit(graph: &Graph) -> PeriodicEdgeMarkovian {
+        let mut periods = vec![1];
+        for &seconds in &[86400.0, 7.0 * 86400.0] {
+            let period = (seconds / graph.time_step).round() as i32;
+
+            if period >= 2 && 2 * period <= graph.duration {
+                periods.push(period);
+            }
+        }
+
+        periods.into_iter()
+            .map(|period| {
+                let model = PeriodicEdgeMarkovian::fit_with_period(graph, period);
+                let bic = model.score(graph).map(|score| score.bic).unwrap_or(f64::INFINITY);
+
+                (model, bic)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(model, _)| model)
+            .unwrap()
+    }
+
+    /// Create a `Graph` from the model, drawing random numbers from `rng`. The pairs linked at
+    /// `t = 0` are given by `initial_state`. `duration` can be longer than the graph the model was
+    /// fitted on
+    ///
+    /// The pairs are generated in parallel by `generate_pairs`.
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        let period = self.period.max(1) as usize;
+        let creation_probability = mean(&self.creation_probability);
+        let deletion_probability = mean(&self.deletion_probability);
+
+        let contacts = generate_pairs(self.number_of_nodes, rng, |pair, rng, contacts| {
+            let linked = self.initial_state
+                .is_linked(pair, creation_probability, deletion_probability, rng);
+
+            generate_step_by_step_pair(
+                pair,
+                |t| {
+                    let phase = t as usize % period;
+                    (self.creation_probability[phase], self.deletion_probability[phase])
+                },
+                linked,
+                self.duration,
+                rng,
+                contacts,
+            );
+        });
+
+        Graph {
+            duration: self.duration,
+            nodes: (1..=self.number_of_nodes).collect(),
+            contacts,
+            ..Default::default()
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "periodic-edge-markovian"
+    }
+
+    fn log_likelihood(&self, graph: &Graph) -> Option<f64> {
+        let period = self.period.max(1) as usize;
+
+        Some(transitions_log_likelihood(
+            graph,
+            |t| self.creation_probability.get(t % period).copied().unwrap_or(0.0),
+            |t| self.deletion_probability.get(t % period).copied().unwrap_or(0.0),
+        ))
+    }
+
+    fn n_parameters(&self) -> usize {
+        2 * self.period.max(1) as usize
+    }
+}
+
+/// Stochastic block Edge-Markovian model properties, where the nodes are split in communities and
+/// the creation/deletion probabilities of a pair depend on the communities of its nodes
+///
+/// The node `i` belongs to the community `communities[i - 1]`, and the probabilities between
+/// the communities `a` and `b` are `creation_probability[a][b]` and `deletion_probability[a][b]`.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct CommunityEdgeMarkovian {
+    pub communities: Vec<usize>,
+    pub creation_probability: Vec<Vec<f32>>,
+    pub deletion_probability: Vec<Vec<f32>>,
+    pub duration: i32,
+    #[cfg_attr(feature = "json", serde(default))]
+    pub initial_state: InitialState,
+}
+
+impl CommunityEdgeMarkovian {
+    /// Maximum likelihood estimation of the probabilities between communities, given the
+    /// community of each node of `graph` (in the order of `graph.nodes`)
+    ///
+    /// The transitions of all the pairs between two communities are counted together, as in
+    /// `EdgeMarkovian::estimate`.
+    pub fn fit_with_communities(graph: &Graph, communities: Vec<usize>) -> CommunityEdgeMarkovian {
+        let blocks = CommunityEdgeMarkovian::block_transitions(graph, &communities);
+
+        CommunityEdgeMarkovian {
+            creation_probability: blocks.iter()
+                .map(|row| row.iter().map(|t| ratio(t.created, t.non_links)).collect())
+                .collect(),
+            deletion_probability: blocks.iter()
+                .map(|row| row.iter().map(|t| ratio(t.deleted, t.links)).collect())
+                .collect(),
+            communities,
+            duration: graph.duration,
+            initial_state: InitialState::Empty,
+        }
+    }
+
+    /// Communities of the nodes of `graph` (in the order of `graph.nodes`) given by their labels.
+    /// The nodes without community are put together in an extra one
+    pub fn communities_from_labels(graph: &Graph, labels: &HashMap<String, String>) -> Vec<usize> {
+        let mut indices: HashMap<&str, usize> = HashMap::new();
+        let communities: Vec<Option<usize>> = graph.nodes.iter()
+            .map(|&node| labels.get(&graph.label(node)).map(|community| {
+                let n_communities = indices.len();
+                *indices.entry(community.as_str()).or_insert(n_communities)
+            }))
+            .collect();
+
+        let unknown = indices.len();
+        communities.into_iter().map(|community| community.unwrap_or(unknown)).collect()
+    }
+
+    /// Infer the communities of the nodes of `graph` (in the order of `graph.nodes`) by label
+    /// propagation on the graph aggregated over time, each pair being weighted by its total
+    /// contact time
+    ///
+    /// Every node starts in its own community, then repeatedly joins the community with the
+    /// highest weight among its neighbours (the smallest one in case of a tie) until no node
+    /// changes. The nodes are visited in order, so that the result is deterministic.
+    pub fn infer_communities(graph: &Graph) -> Vec<usize> {
+        let n = graph.nodes.len();
+        let positions: HashMap<i32, usize> = graph.nodes.iter()
+            .enumerate()
+            .map(|(position, &node)| (node, position))
+            .collect();
+
+        let mut neighbours: Vec<HashMap<usize, i64>> = vec![HashMap::new(); n];
+        for contact in &graph.contacts {
+            let (a, b) = (positions[&contact.couple.0], positions[&contact.couple.1]);
+            let weight = (contact.end - contact.start + 1) as i64;
+
+            *neighbours[a].entry(b).or_insert(0) += weight;
+            *neighbours[b].entry(a).or_insert(0) += weight;
+        }
+
+        let mut communities: Vec<usize> = (0..n).collect();
+        // Label propagation converges quickly, the bound only avoids oscillations
+        for _ in 0..100 {
+            let mut changed = false;
+
+            for node in 0..n {
+                let mut weights: HashMap<usize, i64> = HashMap::new();
+                for (&neighbour, &weight) in &neighbours[node] {
+                    *weights.entry(communities[neighbour]).or_insert(0) += weight;
+                }
+
+                let best = weights.into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+                    .map(|(community, _)| community);
+
+                if let Some(best) = best {
+                    if best != communities[node] {
+                        communities[node] = best;
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        // Number the communities from 0
+        let mut indices: HashMap<usize, usize> = HashMap::new();
+        communities.iter()
+            .map(|&community| {
+                let n_communities = indices.len();
+                *indices.entry(community).or_insert(n_communities)
+            })
+            .collect()
+    }
+
+    /// Transitions of the pairs of `graph` summed by pair of communities, the nodes being
+    /// matched with `communities` by their rank in `graph.nodes`
+    fn block_transitions(graph: &Graph, communities: &[usize]) -> Vec<Vec<Transitions>> {
+        let n_communities = communities.iter().map(|&c| c + 1).max().unwrap_or(0);
+        let duration = graph.duration.max(0) as u64;
+        let positions: HashMap<i32, usize> = graph.nodes.iter()
+            .enumerate()
+            .map(|(position, &node)| (node, position))
+            .collect();
+        let community = |node: i32| positions.get(&node).and_then(|&p| communities.get(p)).copied();
+
+        // Every pair is first counted as never linked
+        let mut sizes = vec![0u64; n_communities];
+        for &c in communities.iter().take(graph.nodes.len()) {
+            sizes[c] += 1;
+        }
+
+        let mut blocks = vec![vec![Transitions::default(); n_communities]; n_communities];
+        for a in 0..n_communities {
+            for b in a..n_communities {
+                let n_pairs = if a == b {
+                    sizes[a] * sizes[a].saturating_sub(1) / 2
+                } else {
+                    sizes[a] * sizes[b]
+                };
+
+                blocks[a][b].non_links = n_pairs * duration;
+            }
+        }
+
+        for (couple, transitions) in graph.pair_transitions() {
+            let (a, b) = match (community(couple.0), community(couple.1)) {
+                (Some(a), Some(b)) => (a.min(b), a.max(b)),
+                _ => continue,
+            };
+            let block = &mut blocks[a][b];
+
+            block.non_links -= duration - transitions.non_links;
+            block.links += transitions.links;
+            block.created += transitions.created;
+            block.deleted += transitions.deleted;
+        }
+
+        // The matrix is symmetric
+        (0..n_communities)
+            .map(|a| (0..n_communities).map(|b| blocks[a.min(b)][a.max(b)]).collect())
+            .collect()
+    }
+}
+
+impl TemporalModel for CommunityEdgeMarkovian {
+    /// Use the communities inferred by `CommunityEdgeMarkovian::infer_communities`
+    fn fit(graph: &Graph) -> CommunityEdgeMarkovian {
+        let communities = CommunityEdgeMarkovian::infer_communities(graph);
+
+        CommunityEdgeMarkovian::fit_with_communities(graph, communities)
+    }
+
+    /// Create a `Graph` from the model as `EdgeMarkovian` does, each pair with the probabilities
+    /// of its communities
+    ///
+    /// The pairs are generated in parallel by `generate_pairs`.
+    fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        let number_of_nodes = self.communities.len() as i32;
+
+        let contacts = generate_pairs(number_of_nodes, rng, |pair, rng, contacts| {
+            let a = self.communities[pair.0 as usize - 1];
+            let b = self.communities[pair.1 as usize - 1];
+            let linked = self.initial_state.is_linked(
+                pair,
+                self.creation_probability[a][b],
+                self.deletion_probability[a][b],
+                rng,
+            );
+
+            generate_edge_markovian_pair(
+                pair,
+                self.creation_probability[a][b],
+                self.deletion_probability[a][b],
+                linked,
+                self.duration,
+                rng,
+                contacts,
+            );
+        });
+
+        Graph {
+            duration: self.duration,
+            nodes: (1..=number_of_nodes).collect(),
+            contacts,
+            ..Default::default()
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "community-edge-markovian"
+    }
+
+    /// The nodes of `graph` are matched with the nodes of the model by their rank
+    fn log_likelihood(&self, graph: &Graph) -> Option<f64> {
+        let blocks = CommunityEdgeMarkovian::block_transitions(graph, &self.communities);
+
+        let mut log_likelihood = 0.0;
+        for (a, row) in blocks.iter().enumerate() {
+            for (b, transitions) in row.iter().enumerate().skip(a) {
+                log_likelihood += bernoulli_log_likelihood(
+                    transitions.created,
+                    transitions.non_links,
+                    self.creation_probability[a][b] as f64,
+                ) + bernoulli_log_likelihood(
+                    transitions.deleted,
+                    transitions.links,
+                    self.deletion_probability[a][b] as f64,
+                );
+            }
+        }
+
+        Some(log_likelihood)
+    }
+
+    /// Two probabilities per pair of communities
+    fn n_parameters(&self) -> usize {
+        let k = self.creation_probability.len();
+
+        k * (k + 1)
+    }
+}
+
+/// Random waypoint mobility model properties
+///
+/// The nodes move in an area of `width` by `height`: each node goes in a straight line to a point
+/// drawn uniformly in the area, at a speed drawn uniformly between `min_speed` and `max_speed`,
+/// waits there for `pause_time` time steps and starts again. Two nodes are linked while they are
+/// at a distance of at most `range`. Speeds are given in distance units per time step.
+///
+/// The model describes the movements of the nodes and cannot be fitted on a graph.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct RandomWaypoint {
+    pub width: f32,
+    pub height: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    pub pause_time: i32,
+    pub range: f32,
+    pub duration: i32,
+    pub number_of_nodes: i32,
+}
+
+impl RandomWaypoint {
+    /// Create a `Graph` from a random waypoint model, drawing random numbers from `rng`
+    ///
+    /// The nodes start at random positions, without pausing, and move at each time step. A
+    /// minimum speed of 0 makes the nodes slow down over time, as the slowest trips last longer.
+    ///
+    /// Every node has its own random stream derived from `rng` and the nodes move in parallel: the
+    /// graph does not depend on the number of threads.
+    pub fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        let seed: u64 = rng.gen();
+        let mut walkers: Vec<Walker> = (1..=self.number_of_nodes)
+            .map(|node| {
+                let mut node_rng = seeded_rng(seed);
+                node_rng.set_stream(node as u64);
+
+                Walker::new(self, node_rng)
+            })
+            .collect();
+
+        // Progress bar
+        let pb = ProgressBar::new(self.duration.max(0) as u64 + 1);
+        pb.set_style(ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.yellow/blue}] {percent}% ({eta})")
+            .progress_chars("#>-"));
+
+        let mut contacts = Vec::new();
+        // Start of the contact of each pair in range at the previous time step
+        let mut open: HashMap<(i32, i32), i32> = HashMap::new();
+
+        for t in 0..=self.duration {
+            if t > 0 {
+                walkers.par_iter_mut().for_each(|walker| walker.step(self));
+            }
+
+            let in_range = self.pairs_in_range(&walkers);
+
+            for (&couple, &start) in &open {
+                if !in_range.contains(&couple) {
+                    contacts.push(Contact {
+                        start,
+                        couple,
+                        end: t - 1,
+                    });
+                }
+            }
+            open.retain(|couple, _| in_range.contains(couple));
+
+            for couple in in_range {
+                open.entry(couple).or_insert(t);
+            }
+
+            pb.inc(1);
+        }
+        pb.finish();
+
+        // The contacts still open at the end are cut at `duration`
+        contacts.extend(open.into_iter().map(|(couple, start)| Contact {
+            start,
+            couple,
+            end: self.duration,
+        }));
+        contacts.sort_by_key(|c| (c.start, c.couple));
+
+        Graph {
+            duration: self.duration,
+            nodes: (1..=self.number_of_nodes).collect(),
+            contacts,
+            ..Default::default()
+        }
+    }
+
+    /// Pairs of nodes at a distance of at most `range`, the node `i` being `walkers[i - 1]`
+    ///
+    /// The nodes are put in a grid of cells of side `range`, so that only the nodes of neighbouring
+    /// cells are compared.
+    fn pairs_in_range(&self, walkers: &[Walker]) -> HashSet<(i32, i32)> {
+        let mut pairs = HashSet::new();
+        if self.range <= 0.0 {
+            return pairs;
+        }
+
+        let range = self.range as f64;
+        let cell = |position: (f64, f64)| {
+            ((position.0 / range).floor() as i64, (position.1 / range).floor() as i64)
+        };
+
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, walker) in walkers.iter().enumerate() {
+            grid.entry(cell(walker.position)).or_default().push(i);
+        }
+
+        for (&(x, y), nodes) in &grid {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let others = match grid.get(&(x + dx, y + dy)) {
+                        Some(others) => others,
+                        None => continue,
+                    };
+
+                    for &i in nodes {
+                        for &j in others.iter().filter(|&&j| j > i) {
+                            let (a, b) = (walkers[i].position, walkers[j].position);
+
+                            if (a.0 - b.0).hypot(a.1 - b.1) <= range {
+                                pairs.insert((i as i32 + 1, j as i32 + 1));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        pairs
+    }
+}
+
+/// Create a `Graph` from the model, seeding the random generator from the system
+impl From<RandomWaypoint> for Graph {
+    fn from(model: RandomWaypoint) -> Graph {
+        model.generate(&mut rand::thread_rng())
+    }
+}
+
+/// A node of the random waypoint model
+struct Walker {
+    position: (f64, f64),
+    waypoint: (f64, f64),
+    speed: f64,
+    /// Time steps left before leaving the current position
+    pause: i32,
+    rng: ChaCha8Rng,
+}
+
+impl Walker {
+    /// Put a node at a random position of the area of `model`, heading to its first waypoint
+    fn new(model: &RandomWaypoint, rng: ChaCha8Rng) -> Walker {
+        let mut walker = Walker {
+            position: (0.0, 0.0),
+            waypoint: (0.0, 0.0),
+            speed: 0.0,
+            pause: 0,
+            rng,
+        };
+        walker.position = walker.random_point(model);
+        walker.next_waypoint(model);
+
+        walker
+    }
+
+    /// Point drawn uniformly in the area of `model`
+    fn random_point(&mut self, model: &RandomWaypoint) -> (f64, f64) {
+        (
+            self.rng.gen::<f64>() * model.width as f64,
+            self.rng.gen::<f64>() * model.height as f64,
+        )
+    }
+
+    /// Draw the next waypoint and the speed to reach it
+    fn next_waypoint(&mut self, model: &RandomWaypoint) {
+        self.waypoint = self.random_point(model);
+        self.speed = model.min_speed as f64
+            + (model.max_speed - model.min_speed) as f64 * self.rng.gen::<f64>();
+    }
+
+    /// Move the node during one time step. Reaching its waypoint, the node stops there and pauses
+    fn step(&mut self, model: &RandomWaypoint) {
+        if self.pause > 0 {
+            self.pause -= 1;
+            return;
+        }
+
+        let (dx, dy) = (self.waypoint.0 - self.position.0, self.waypoint.1 - self.position.1);
+        let distance = dx.hypot(dy);
+
+        if distance <= self.speed {
+            self.position = self.waypoint;
+            self.pause = model.pause_time;
+            self.next_waypoint(model);
+        } else {
+            self.position.0 += dx * self.speed / distance;
+            self.position.1 += dy * self.speed / distance;
+        }
+    }
+}
+
+/// A model along with its fitted parameters
+///
+/// With the `json` feature, it can be saved next to the dataset it was fitted on and reloaded
+/// later to generate new graphs.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(tag = "model"))]
+#[allow(clippy::enum_variant_names)]
+pub enum FittedModel {
+    EdgeMarkovian(EdgeMarkovian),
+    TimeDependentEdgeMarkovian(TimeDependentEdgeMarkovian),
+    SemiMarkovEdgeModel(SemiMarkovEdgeModel),
+    HeterogeneousEdgeMarkovian(HeterogeneousEdgeMarkovian),
+    ActivityDriven(ActivityDriven),
+    PeriodicEdgeMarkovian(PeriodicEdgeMarkovian),
+    CommunityEdgeMarkovian(CommunityEdgeMarkovian),
+}
+
+#[cfg(feature = "json")]
+impl FittedModel {
+    /// Read a model saved with `FittedModel::save`
+    pub fn from_file(filename: &str) -> Result<FittedModel, io::Error> {
+        let text = fs::read_to_string(filename)?;
+
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Save the model and its parameters as JSON
+    pub fn save(&self, filename: &str) -> Result<(), io::Error> {
+        fs::write(filename, serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl FittedModel {
+    /// The fitted model, to use it through `TemporalModel`
+    pub fn model(&self) -> &dyn TemporalModel {
+        match self {
+            FittedModel::EdgeMarkovian(model) => model,
+            FittedModel::TimeDependentEdgeMarkovian(model) => model,
+            FittedModel::SemiMarkovEdgeModel(model) => model,
+            FittedModel::HeterogeneousEdgeMarkovian(model) => model,
+            FittedModel::ActivityDriven(model) => model,
+            FittedModel::PeriodicEdgeMarkovian(model) => model,
+            FittedModel::CommunityEdgeMarkovian(model) => model,
+        }
+    }
+
+    /// Create a `Graph` from the model, drawing random numbers from `rng`
+    pub fn generate(&self, rng: &mut dyn RngCore) -> Graph {
+        self.model().generate(rng)
+    }
+
+    /// Set the state of the pairs at `t = 0` in the generated graphs. The semi-Markov and
+    /// activity-driven models draw their first contacts from their own laws and ignore it
+    pub fn set_initial_state(&mut self, initial_state: InitialState) {
+        match self {
+            FittedModel::EdgeMarkovian(model) => model.initial_state = initial_state,
+            FittedModel::TimeDependentEdgeMarkovian(model) => model.initial_state = initial_state,
+            FittedModel::HeterogeneousEdgeMarkovian(model) => model.initial_state = initial_state,
+            FittedModel::PeriodicEdgeMarkovian(model) => model.initial_state = initial_state,
+            FittedModel::CommunityEdgeMarkovian(model) => model.initial_state = initial_state,
+            FittedModel::SemiMarkovEdgeModel(_) | FittedModel::ActivityDriven(_) => {}
+        }
+    }
+}
+
+/// Options used when fitting a model with `ModelKind::fit`
+#[derive(Debug, Clone, Default)]
+pub struct FitOptions {
+    /// Period of the periodic models, in time steps. Chosen from the graph by default
+    pub period: Option<i32>,
+    /// Duration of the graphs generated by the periodic models, which can be longer than the
+    /// fitted graph. Defaults to the duration of the fitted graph
+    pub duration: Option<i32>,
+    /// Family of the laws of the contact and inter-contact durations of the semi-Markov model.
+    /// Defaults to the empirical laws
+    pub duration_law: Option<DurationLawKind>,
+    /// Community of each node of the community model, given by node label. Inferred from the
+    /// graph by default
+    pub communities: Option<HashMap<String, String>>,
+    /// State of the pairs at `t = 0` in the graphs generated by the Edge-Markovian models, the
+    /// first snapshot being copied from the fitted graph. No pair is linked by default
+    pub initial_state: Option<InitialStateKind>,
+}
+
+/// The models that can be fitted on a graph
+#[derive(Debug, Clone, Copy)]
+#[allow(clippy::enum_variant_names)]
+pub enum ModelKind {
+    EdgeMarkovian,
+    TimeDependentEdgeMarkovian,
+    SemiMarkovEdgeModel,
+    HeterogeneousEdgeMarkovian,
+    ActivityDriven,
+    PeriodicEdgeMarkovian,
+    CommunityEdgeMarkovian,
+}
+
+impl FromStr for ModelKind {
+    type Err = String;
+
+    /// Parse a model name as returned by `TemporalModel::name`, or its number (`1` to `7`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" | "edge-markovian" => Ok(ModelKind::EdgeMarkovian),
+            "2" | "time-dependent-edge-markovian" => Ok(ModelKind::TimeDependentEdgeMarkovian),
+            "3" | "semi-markov" => Ok(ModelKind::SemiMarkovEdgeModel),
+            "4" | "heterogeneous-edge-markovian" => Ok(ModelKind::HeterogeneousEdgeMarkovian),
+            "5" | "activity-driven" => Ok(ModelKind::ActivityDriven),
+            "6" | "periodic-edge-markovian" => Ok(ModelKind::PeriodicEdgeMarkovian),
+            "7" | "community-edge-markovian" => Ok(ModelKind::CommunityEdgeMarkovian),
+            _ => Err(format!("unknown model: {}", s)),
+        }
+    }
+}
+
+impl ModelKind {
+    /// Every model, in the order of their numbers
+    pub const ALL: &'static [ModelKind] = &[
+        ModelKind::EdgeMarkovian,
+        ModelKind::TimeDependentEdgeMarkovian,
+        ModelKind::SemiMarkovEdgeModel,
+        ModelKind::HeterogeneousEdgeMarkovian,
+        ModelKind::ActivityDriven,
+        ModelKind::PeriodicEdgeMarkovian,
+        ModelKind::CommunityEdgeMarkovian,
+    ];
+
+    /// Fit the model on `graph`
+    pub fn fit(self, graph: &Graph, options: &FitOptions) -> FittedModel {
+        let mut fitted = match self {
+            ModelKind::EdgeMarkovian => FittedModel::EdgeMarkovian(TemporalModel::fit(graph)),
+            ModelKind::TimeDependentEdgeMarkovian => {
+                FittedModel::TimeDependentEdgeMarkovian(TemporalModel::fit(graph))
+            }
+            ModelKind::SemiMarkovEdgeModel => FittedModel::SemiMarkovEdgeModel(
+                SemiMarkovEdgeModel::fit_with_law(
+                    graph,
+                    options.duration_law.unwrap_or(DurationLawKind::Empirical),
+                )
+            ),
+            ModelKind::HeterogeneousEdgeMarkovian => {
+                FittedModel::HeterogeneousEdgeMarkovian(TemporalModel::fit(graph))
+            }
+            ModelKind::ActivityDriven => FittedModel::ActivityDriven(TemporalModel::fit(graph)),
+            ModelKind::PeriodicEdgeMarkovian => {
+                let mut model: PeriodicEdgeMarkovian = match options.period {
+                    Some(period) => PeriodicEdgeMarkovian::fit_with_period(graph, period),
+                    None => TemporalModel::fit(graph),
+                };
+                if let Some(duration) = options.duration {
+                    model.duration = duration;
+                }
+
+                FittedModel::PeriodicEdgeMarkovian(model)
+            }
+            ModelKind::CommunityEdgeMarkovian => {
+                let communities = match &options.communities {
+                    Some(labels) => CommunityEdgeMarkovian::communities_from_labels(graph, labels),
+                    None => CommunityEdgeMarkovian::infer_communities(graph),
+                };
+
+                FittedModel::CommunityEdgeMarkovian(
+                    CommunityEdgeMarkovian::fit_with_communities(graph, communities)
+                )
+            }
+        };
+
+        if let Some(kind) = options.initial_state {
+            fitted.set_initial_state(InitialState::new(kind, graph));
+        }
+
+        fitted
+    }
+}
+
+/// Create a `Graph` from any fitted model, seeding the random generator from the system
+impl From<FittedModel> for Graph {
+    fn from(model: FittedModel) -> Graph {
+        model.generate(&mut rand::thread_rng())
+    }
+}
+
+/// Generate the contacts of every pair of nodes `(n1, n2)`, with `1 <= n1 < n2 <= n`, in parallel
+///
+/// `generate_pair` pushes the contacts of a pair, drawing its random numbers from the generator it
+/// is given. Every pair has its own stream of a generator seeded from `rng`, so that the contacts
+/// only depend on `rng` and not on the way the pairs are split between threads. The contacts are
+/// sorted by start time, then by pair.
+fn generate_pairs<R, F>(n: i32, rng: &mut R, generate_pair: F) -> Vec<Contact>
+where
+    R: Rng + ?Sized,
+    F: Fn((i32, i32), &mut ChaCha8Rng, &mut Vec<Contact>) + Sync,
+{
+    let seed: u64 = rng.gen();
+
+    // Progress bar
+    let pb = ProgressBar::new(n as u64);
+    pb.set_style(ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.yellow/blue}] {percent}% ({eta})")
+        .progress_chars("#>-"));
+
+    let rows: Vec<Vec<Contact>> = (1..=n).into_par_iter()
+        .map(|i_node1| {
+            let mut contacts = Vec::new();
+
+            for i_node2 in (i_node1 + 1)..=n {
+                let mut pair_rng = seeded_rng(seed);
+                pair_rng.set_stream(((i_node1 as u64) << 32) | i_node2 as u64);
+
+                generate_pair((i_node1, i_node2), &mut pair_rng, &mut contacts);
+            }
+
+            pb.inc(1);
+            contacts
+        })
+        .collect();
+    pb.finish();
+
+    let mut contacts: Vec<Contact> = rows.into_iter().flatten().collect();
+    contacts.sort_by_key(|c| c.start);
+
+    contacts
+}
+
+/// Link intervals of `graph` (see `Graph::link_intervals`) grouped by pair
+fn pair_intervals(graph: &Graph) -> Vec<Vec<Contact>> {
+    let mut pairs: Vec<Vec<Contact>> = Vec::new();
+
+    for interval in graph.link_intervals() {
+        match pairs.last_mut() {
+            Some(intervals) if intervals[0].couple == interval.couple => intervals.push(interval),
+            _ => pairs.push(vec![interval]),
+        }
+    }
+
+    pairs
+}
+
+/// Generate the contacts of `pairs` in parallel, as `generate_pairs` does
+///
+/// Every pair has its own stream of a generator seeded from `rng`, chosen from its position in
+/// `pairs`.
+fn generate_listed_pairs<R, T, F>(pairs: &[T], rng: &mut R, generate_pair: F) -> Vec<Contact>
+where
+    R: Rng + ?Sized,
+    T: Sync,
+    F: Fn(&T, &mut ChaCha8Rng, &mut Vec<Contact>) + Sync,
+{
+    let seed: u64 = rng.gen();
+
+    // Progress bar
+    let pb = ProgressBar::new(pairs.len() as u64);
+    pb.set_style(ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.yellow/blue}] {percent}% ({eta})")
+        .progress_chars("#>-"));
+
+    let pair_contacts: Vec<Vec<Contact>> = pairs.par_iter()
+        .enumerate()
+        .map(|(i_pair, pair)| {
+            let mut contacts = Vec::new();
+
+            let mut pair_rng = seeded_rng(seed);
+            pair_rng.set_stream(i_pair as u64);
+            generate_pair(pair, &mut pair_rng, &mut contacts);
+
+            pb.inc(1);
+            contacts
+        })
+        .collect();
+    pb.finish();
+
+    let mut contacts: Vec<Contact> = pair_contacts.into_iter().flatten().collect();
+    contacts.sort_by_key(|c| c.start);
+
+    contacts
+}
+
+/// Generate the contacts of a pair of nodes following an Edge-Markovian model, the pair being
+/// created with probability `creation_probability` and deleted with probability
+/// `deletion_probability` at each time step. The pair is linked at `t = 0` if `linked` is true
+///
+/// The time before the next creation or deletion is drawn from a geometric law. The contact still
+/// open at the end is cut at `duration`.
+fn generate_edge_markovian_pair<R: Rng + ?Sized>(
+    couple: (i32, i32),
+    creation_probability: f32,
+    deletion_probability: f32,
+    linked: bool,
+    duration: i32,
+    rng: &mut R,
+    contacts: &mut Vec<Contact>,
+) {
+    // The pair is created at each step following the last one at which it is not linked with
+    // probability p
+    let mut start = if linked {
+        0
+    } else {
+        geometric(rng, creation_probability)
+    };
+
+    while start <= duration {
+        // The pair is deleted at each following step with probability d, `end` being the last
+        // step at which it is linked
+        let end = start.saturating_add(geometric(rng, deletion_probability) - 1);
+
+        contacts.push(Contact {
+            start,
+            couple,
+            end: end.min(duration),
+        });
+
+        start = end.saturating_add(1).saturating_add(geometric(rng, creation_probability));
+    }
+}
+
+/// Generate the contacts of a pair of nodes following an Edge-Markovian model whose probabilities
+/// change over time, `probabilities(t)` giving the creation and deletion probabilities from the
+/// step `t - 1` to the step `t`. The pair is linked at `t = 0` if `linked` is true
+///
+/// A random number is drawn at each time step to create the pair if it is not linked, or delete
+/// it otherwise. The contact still open at the end is cut at `duration`.
+fn generate_step_by_step_pair<R, P>(
+    couple: (i32, i32),
+    probabilities: P,
+    linked: bool,
+    duration: i32,
+    rng: &mut R,
+    contacts: &mut Vec<Contact>,
+)
+where
+    R: Rng + ?Sized,
+    P: Fn(i32) -> (f32, f32),
+{
+    // Start of the current contact of the pair, if connected
+    let mut connected: Option<i32> = if linked { Some(0) } else { None };
+
+    for t in 1..=duration {
+        let (creation_probability, deletion_probability) = probabilities(t);
+        // Generate number in (0, 1[
+        let rand_num: f32 = rng.gen();
+
+        match connected {
+            // If (n_1, n_2) is in E_{t-1}, delete pair with probability d
+            Some(start) => if rand_num <= deletion_probability {
+                contacts.push(Contact {
+                    start,
+                    couple,
+                    end: t - 1,
+                });
+
+                connected = None;
+            },
+            // If (n_1, n_2) is not in E_{t-1}, create pair with probability p
+            None => if rand_num <= creation_probability {
+                connected = Some(t);
+            },
+        }
+    }
+
+    // The contact still open at the end is cut at `duration`
+    if let Some(start) = connected {
+        contacts.push(Contact {
+            start,
+            couple,
+            end: duration,
+        });
+    }
+}
+
+/// Maximum likelihood estimation of the creation and deletion probabilities at each time step of
+/// `graph`: the number of pairs created (or deleted) at a step divided by the number of pairs that
+/// could have been. The probabilities are 0 when no pair could have been created (or deleted)
+fn step_probabilities(graph: &Graph) -> (Vec<f32>, Vec<f32>) {
+    graph.transitions().iter()
+        .map(|transitions| (
+            ratio(transitions.created, transitions.non_links),
+            ratio(transitions.deleted, transitions.links),
+        ))
+        .unzip()
+}
+
+/// Log-likelihood of the transitions between the snapshots of `graph`, the pairs being created
+/// with probability `creation_probability(t)` and deleted with probability
+/// `deletion_probability(t)` from the step `t - 1` to the step `t`
+fn transitions_log_likelihood<C, D>(graph: &Graph, creation_probability: C, deletion_probability: D) -> f64
+where
+    C: Fn(usize) -> f32,
+    D: Fn(usize) -> f32,
+{
+    graph.transitions().iter()
+        .enumerate()
+        .map(|(t, transitions)| {
+            bernoulli_log_likelihood(
+                transitions.created,
+                transitions.non_links,
+                creation_probability(t) as f64,
+            ) + bernoulli_log_likelihood(
+                transitions.deleted,
+                transitions.links,
+                deletion_probability(t) as f64,
+            )
+        })
+        .sum()
+}
+
+/// Maximum likelihood estimation of the probability of success of `trials` Bernoulli trials with
+/// `successes` successes, 0 if there is no trial
+fn ratio(successes: u64, trials: u64) -> f32 {
+    if trials == 0 {
+        0.0
+    } else {
+        (successes as f64 / trials as f64) as f32
+    }
+}
+
+/// Mean of `values`, 0 if there is none
+fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+}
+
+/// Log-likelihood of `successes` successes out of `trials` Bernoulli trials of probability `p`
+fn bernoulli_log_likelihood(successes: u64, trials: u64, p: f64) -> f64 {
+    // 0 * ln(0) is taken as 0
+    let term = |count: u64, p: f64| if count == 0 { 0.0 } else { count as f64 * p.ln() };
+
+    term(successes, p) + term(trials - successes, 1.0 - p)
+}
+
+/// Wilson score interval at 95% of the probability of success of `trials` Bernoulli trials with
+/// `successes` successes. It stays in `[0, 1]` and does not vanish when there is no success
+fn wilson_interval(successes: u64, trials: u64) -> (f32, f32) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+
+    const Z: f64 = 1.96;
+    let n = trials as f64;
+    let p = successes as f64 / n;
+
+    let center = (p + Z * Z / (2.0 * n)) / (1.0 + Z * Z / n);
+    let half_width = Z / (1.0 + Z * Z / n) * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt();
+
+    ((center - half_width).max(0.0) as f32, (center + half_width).min(1.0) as f32)
+}
+
+/// Draw the number of trials needed to get a success with probability `p`, i.e. a value of the
+/// geometric law of parameter `p` (at least 1). Never happening events take `i32::MAX` trials
+fn geometric<R: Rng + ?Sized>(rng: &mut R, p: f32) -> i32 {
+    if p <= 0.0 {
+        return i32::MAX;
+    }
+
+    // Number in ]0, 1]
+    let rand_num: f64 = 1.0 - rng.gen::<f64>();
+    let trials = (rand_num.ln() / (1.0 - p as f64).ln()).ceil();
+
+    // The cast saturates at i32::MAX
+    (trials as i32).max(1)
+}
+
+/// Random generator used for reproducible simulations
+///
+/// ChaCha8 is portable and its output for a given seed does not depend on the version of `rand`.
+pub fn seeded_rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Number of contacts, mean contact duration and mean inter-contact duration of the contacts
+    /// of a single pair, sorted by start time
+    fn pair_statistics(contacts: &[Contact]) -> (usize, f64, f64) {
+        let durations: f64 = contacts.iter().map(|c| (c.end - c.start + 1) as f64).sum();
+        let inter_contacts: f64 = contacts.windows(2).map(|w| (w[1].start - w[0].end - 1) as f64).sum();
+
+        (
+            contacts.len(),
+            durations / contacts.len() as f64,
+            inter_contacts / (contacts.len() - 1) as f64,
+        )
+    }
+
+    #[test]
+    fn edge_markovian_pair_matches_step_by_step() {
+        let duration = 1_000_000;
+        let tolerance = 0.05;
+
+        for (seed, &(p, d)) in [(0.05, 0.2), (0.5, 0.5), (0.01, 0.9)].iter().enumerate() {
+            let mut event_driven = Vec::new();
+            let mut rng = seeded_rng(seed as u64);
+            generate_edge_markovian_pair((1, 2), p, d, false, duration, &mut rng, &mut event_driven);
+
+            let mut step_by_step = Vec::new();
+            let mut rng = seeded_rng(seed as u64 + 100);
+            generate_step_by_step_pair((1, 2), |_| (p, d), false, duration, &mut rng, &mut step_by_step);
+
+            let (count, mean_duration, mean_inter_contact) = pair_statistics(&event_driven);
+            let (expected_count, expected_duration, expected_inter_contact) = pair_statistics(&step_by_step);
+
+            let close = |value: f64, expected: f64| (value - expected).abs() <= tolerance * expected;
+            assert!(close(count as f64, expected_count as f64),
+                "p = {}, d = {}: {} contacts instead of {}", p, d, count, expected_count);
+            assert!(close(mean_duration, expected_duration),
+                "p = {}, d = {}: mean duration {} instead of {}", p, d, mean_duration, expected_duration);
+            assert!(close(mean_inter_contact, expected_inter_contact),
+                "p = {}, d = {}: mean inter-contact {} instead of {}", p, d, mean_inter_contact, expected_inter_contact);
+        }
+    }
+}
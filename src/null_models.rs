@@ -0,0 +1,187 @@
+use std::str::FromStr;
+
+use rand::{
+    prelude::*,
+    seq::index,
+};
+
+use crate::graph::{Contact, Graph};
+
+
+/// Randomised reference models of a temporal graph
+///
+/// Each null model shuffles the contacts of a graph while keeping some of its properties, to
+/// tell whether a property of the original graph is significant or only follows from the kept
+/// ones.
+#[derive(Debug, Clone, Copy)]
+pub enum NullModel {
+    /// Shuffle the start times of the contacts. Each contact keeps its pair and its duration,
+    /// and is cut at the end of the graph. The contacts of a pair that overlap after the shuffle
+    /// are merged, as in `Graph::link_intervals`, rather than drawn again: the pair is then linked
+    /// for less time than in the original graph
+    StartTimes,
+    /// Shuffle the inter-contact durations of each pair. Each pair keeps its first contact start,
+    /// its contact durations and the time span of its contacts
+    InterContacts,
+    /// Move the contacts of each linked pair to a random pair of nodes, two linked pairs never
+    /// being moved to the same one. The timings of the contacts are kept
+    Pairs,
+    /// Shuffle the snapshots: the pairs linked in each snapshot are kept, but the order of the
+    /// snapshots is random
+    Snapshots,
+}
+
+impl FromStr for NullModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "start-times" => Ok(NullModel::StartTimes),
+            "inter-contacts" => Ok(NullModel::InterContacts),
+            "pairs" => Ok(NullModel::Pairs),
+            "snapshots" => Ok(NullModel::Snapshots),
+            _ => Err(format!("unknown null model: {}", s)),
+        }
+    }
+}
+
+impl NullModel {
+    /// Create a randomised version of `graph`, drawing random numbers from `rng`
+    ///
+    /// The randomised graph has the same nodes, labels, duration and time samples as `graph`.
+    /// Its contacts never overlap for a same pair, and are sorted by start time, then by pair.
+    pub fn randomise(self, graph: &Graph, rng: &mut dyn RngCore) -> Graph {
+        let contacts = match self {
+            NullModel::StartTimes => shuffle_start_times(graph, rng),
+            NullModel::InterContacts => shuffle_inter_contacts(graph, rng),
+            NullModel::Pairs => rewire_pairs(graph, rng),
+            NullModel::Snapshots => shuffle_snapshots(graph, rng),
+        };
+
+        let mut randomised = Graph {
+            nodes: graph.nodes.clone(),
+            contacts,
+            duration: graph.duration,
+            labels: graph.labels.clone(),
+            epoch: graph.epoch,
+            time_step: graph.time_step,
+            seed: None,
+        };
+        // Only the shuffled start times can make the contacts of a pair overlap
+        if let NullModel::StartTimes = self {
+            randomised.contacts = randomised.link_intervals();
+        }
+        randomised.contacts.sort_by_key(|c| (c.start, c.couple));
+
+        randomised
+    }
+}
+
+/// Give each contact of `graph` the start time of a random contact
+fn shuffle_start_times(graph: &Graph, rng: &mut dyn RngCore) -> Vec<Contact> {
+    let mut starts: Vec<i32> = graph.contacts.iter().map(|c| c.start).collect();
+    starts.shuffle(rng);
+
+    graph.contacts.iter()
+        .zip(starts)
+        .map(|(contact, start)| Contact {
+            start,
+            couple: contact.couple,
+            end: (start + contact.end - contact.start).min(graph.duration),
+        })
+        .collect()
+}
+
+/// Shuffle the time between consecutive link intervals of each pair of `graph`
+fn shuffle_inter_contacts(graph: &Graph, rng: &mut dyn RngCore) -> Vec<Contact> {
+    let intervals = graph.link_intervals();
+    let mut contacts = Vec::with_capacity(intervals.len());
+
+    for pair in intervals.chunk_by(|a, b| a.couple == b.couple) {
+        // Time from the end of an interval to the start of the next one
+        let mut gaps: Vec<i32> = pair.windows(2).map(|w| w[1].start - w[0].end).collect();
+        gaps.shuffle(rng);
+
+        let mut start = pair[0].start;
+        for (i, interval) in pair.iter().enumerate() {
+            let end = start + interval.end - interval.start;
+            contacts.push(Contact {
+                start,
+                couple: interval.couple,
+                end,
+            });
+
+            if let Some(gap) = gaps.get(i) {
+                start = end + gap;
+            }
+        }
+    }
+
+    contacts
+}
+
+/// Move the link intervals of each linked pair of `graph` to a distinct pair drawn at random
+fn rewire_pairs(graph: &Graph, rng: &mut dyn RngCore) -> Vec<Contact> {
+    let intervals = graph.link_intervals();
+    let pairs: Vec<&[Contact]> = intervals.chunk_by(|a, b| a.couple == b.couple).collect();
+
+    let mut nodes = graph.nodes.clone();
+    nodes.sort_unstable();
+    let n = nodes.len();
+    let n_pairs = n * n.saturating_sub(1) / 2;
+
+    // The pairs are numbered row by row: (nodes[0], nodes[1]), (nodes[0], nodes[2])...
+    let couple = |mut position: usize| {
+        let mut i = 0;
+        while position >= n - 1 - i {
+            position -= n - 1 - i;
+            i += 1;
+        }
+
+        (nodes[i], nodes[i + 1 + position])
+    };
+
+    index::sample(rng, n_pairs, pairs.len().min(n_pairs)).iter()
+        .zip(pairs)
+        .flat_map(|(position, pair)| {
+            let couple = couple(position);
+
+            pair.iter().map(move |interval| Contact {
+                start: interval.start,
+                couple,
+                end: interval.end,
+            })
+        })
+        .collect()
+}
+
+/// Permute the snapshots `0` to `duration` of `graph`
+fn shuffle_snapshots(graph: &Graph, rng: &mut dyn RngCore) -> Vec<Contact> {
+    let duration = graph.duration.max(0);
+    let mut times: Vec<i32> = (0..=duration).collect();
+    times.shuffle(rng);
+
+    // Pairs linked in each snapshot, at their new time
+    let mut links: Vec<((i32, i32), i32)> = Vec::new();
+    for interval in graph.link_intervals() {
+        for t in interval.start.max(0)..=interval.end.min(duration) {
+            links.push((interval.couple, times[t as usize]));
+        }
+    }
+    links.sort_unstable();
+
+    // Merge the consecutive snapshots of each pair
+    let mut contacts: Vec<Contact> = Vec::new();
+    for (couple, t) in links {
+        match contacts.last_mut() {
+            Some(last) if last.couple == couple && last.end + 1 == t => last.end = t,
+            _ => contacts.push(Contact {
+                start: t,
+                couple,
+                end: t,
+            }),
+        }
+    }
+
+    contacts
+}